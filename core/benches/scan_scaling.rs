@@ -0,0 +1,36 @@
+use std::{path::Path, time::Instant};
+
+use rolldown::{graph::Graph, types::NormalizedInputOptions};
+
+// Builds the module graph of three.js with an increasing number of threads.
+// three.js needs to be cloned into `benchmark/three.js` first:
+// cargo bench --bench scan_scaling [-- entry]
+fn main() {
+  // `cargo bench` passes `--bench` to the binary.
+  let entry = std::env::args()
+    .skip(1)
+    .find(|arg| !arg.starts_with("--"))
+    .unwrap_or_else(|| "../benchmark/three.js/src/Three.js".to_owned());
+  // Nothing to measure without three.js.
+  if !Path::new(&entry).is_file() {
+    return;
+  }
+
+  let mut threads = 1;
+  while threads <= num_cpus::get() {
+    let start = Instant::now();
+    let mut graph = Graph::new(NormalizedInputOptions {
+      input: vec![entry.clone()],
+      max_parallel_file_reads: Some(threads),
+      ..Default::default()
+    });
+    graph.build().expect("failed to build three.js");
+    println!(
+      "{:>3} threads: {:>8.2}ms for {} modules",
+      threads,
+      start.elapsed().as_secs_f64() * 1000.0,
+      graph.module_by_id.len()
+    );
+    threads *= 2;
+  }
+}
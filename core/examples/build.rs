@@ -9,5 +9,7 @@ fn main() {
   let mut graph = Graph::from_single_entry("../node_modules/lodash-es/lodash.js".to_owned());
   // let mut graph = GraphContainer::new("./tests/fixtures/conflicted/index.js".to_owned());
   // let mut graph = GraphContainer::new("./tests/fixtures/inter_module/index.js".to_owned());
-  graph.build();
+  if let Err(err) = graph.build() {
+    eprintln!("{}", err);
+  }
 }
@@ -1,6 +1,7 @@
 use std::time::Instant;
 
 use rolldown::{
+  error::RolldownError,
   types::{NormalizedInputOptions, NormalizedOutputOptions},
   RolldownBuild,
 };
@@ -29,16 +30,25 @@ fn main() {
       // "./tests/fixtures/preact/index.js".to_owned(),
       // "./tests/fixtures/tree-shaking/index.js".to_owned(),
     ],
-    treeshake: true,
-  });
-  let output = rolldown_build.write(NormalizedOutputOptions {
-    // entry_file_names: "[name].js".to_string(),
-    file: Some("./output.js".to_string()),
-    // dir: Some("./output.js".to_string()),
+    treeshake: Some(Default::default()),
     ..Default::default()
-  });
+  })
+  .unwrap_or_else(exit_with_error);
+  let output = rolldown_build
+    .write(NormalizedOutputOptions {
+      // entry_file_names: "[name].js".to_string(),
+      file: Some("./output.js".to_string()),
+      // dir: Some("./output.js".to_string()),
+      ..Default::default()
+    })
+    .unwrap_or_else(exit_with_error);
 
   log::info!("output:\n{:#?}", output);
   println!("{}", output[0].get_content());
   // println!("gen() finished in {}", start.elapsed().as_millis());
 }
+
+fn exit_with_error(err: RolldownError) -> ! {
+  eprintln!("{}", err);
+  std::process::exit(1)
+}
@@ -2,7 +2,13 @@ use std::collections::HashMap;
 
 use dashmap::DashSet;
 
-use crate::{chunk::Chunk, graph, structs::OutputChunk, types::NormalizedOutputOptions};
+use crate::{
+  chunk::Chunk,
+  graph,
+  structs::OutputChunk,
+  types::NormalizedOutputOptions,
+  warning::{Warning, WarningCode},
+};
 
 #[non_exhaustive]
 pub struct Bundle {
@@ -33,6 +39,7 @@ impl Bundle {
         .clone()
         .into_iter()
         .map(|idx| self.graph.module_graph[idx].clone())
+        .filter(|id| self.graph.module_by_id.contains_key(id))
         .collect(),
       symbol_box: self.graph.symbol_box.clone(),
       entries,
@@ -55,7 +62,23 @@ impl Bundle {
     chunks
       .iter_mut()
       .map(|chunk| {
-        let chunk = chunk.render(&self.output_options, &mut self.graph.module_by_id);
+        let is_empty = chunk.order_modules.iter().all(|id| {
+          self.graph.module_by_id[id]
+            .statements
+            .iter()
+            .all(|stmt| !stmt.included)
+        });
+        if is_empty {
+          self.graph.warn(Warning::new(
+            WarningCode::EmptyBundle,
+            format!("Generated an empty chunk: \"{}\"", chunk.id),
+          ));
+        }
+        let chunk = chunk.render(
+          &self.output_options,
+          &mut self.graph.module_by_id,
+          &self.graph.external_by_id,
+        );
         (
           chunk.file_name.clone(),
           OutputChunk {
@@ -9,12 +9,13 @@ use std::{
 
 use crate::{
   compiler::SOURCE_MAP,
+  external_module::ExternalModule,
   module::Module,
   renamer::Renamer,
   structs::{OutputChunk, RenderedChunk},
   symbol_box::SymbolBox,
   types::NormalizedOutputOptions,
-  utils::lcp,
+  utils::{lcp, path::common_dir},
 };
 
 use rayon::prelude::*;
@@ -46,7 +47,13 @@ impl Chunk {
   }
 
   pub fn de_conflict(&mut self, modules: &mut HashMap<SmolStr, Box<Module>>) {
-    let mut used_names = HashSet::new();
+    // Renaming a declaration to a global accessed by any module would shadow the global.
+    let mut used_names = self
+      .order_modules
+      .iter()
+      .flat_map(|id| modules[id].global_reads.iter())
+      .map(|name| name.to_string())
+      .collect::<HashSet<_>>();
     let mut mark_to_name = HashMap::new();
 
     // De-conflict from the entry module to keep namings as simple as possible
@@ -56,7 +63,11 @@ impl Chunk {
       .map(|id| modules.get(id).unwrap())
       .rev()
       .for_each(|module| {
-        module.declared_symbols.iter().for_each(|(name, mark)| {
+        // Names only depend on the order of modules and declarations, never on the iteration order
+        // of `HashMap`s.
+        let mut declared_symbols = module.declared_symbols.iter().collect::<Vec<_>>();
+        declared_symbols.sort_by(|(a, _), (b, _)| a.cmp(b));
+        declared_symbols.into_iter().for_each(|(name, mark)| {
           let root_mark = self.symbol_box.lock().unwrap().find_root(*mark);
           if let std::collections::hash_map::Entry::Vacant(e) = mark_to_name.entry(root_mark) {
             let original_name = name.to_string();
@@ -90,10 +101,22 @@ impl Chunk {
     &mut self,
     options: &NormalizedOutputOptions,
     modules: &mut HashMap<SmolStr, Box<Module>>,
+    external_modules: &HashMap<SmolStr, ExternalModule>,
   ) -> RenderedChunk {
     assert!(!self.id.is_empty());
+    let entries = self
+      .entries
+      .iter()
+      .map(|id| id.to_string())
+      .collect::<Vec<_>>();
+    let input_base = common_dir(entries.iter().map(|id| id.as_str()));
     modules.par_iter_mut().for_each(|(_key, module)| {
       module.trim_exports();
+      module.set_external_import_paths(|id| {
+        external_modules
+          .get(id)
+          .map(|external_module| self.get_import_path(external_module, &input_base, options))
+      });
       if module.is_user_defined_entry_point {
         module.generate_exports();
       }
@@ -154,6 +177,34 @@ impl Chunk {
     }
   }
 
+  // Rollup renders a renormalized external relative to the output chunk, as if the output
+  // directory mirrored the common directory of the entries.
+  fn get_import_path(
+    &self,
+    external_module: &ExternalModule,
+    input_base: &str,
+    options: &NormalizedOutputOptions,
+  ) -> String {
+    if let Some(path) = options
+      .paths
+      .as_ref()
+      .and_then(|paths| paths.get(&external_module.id))
+    {
+      return path;
+    }
+    if external_module.renormalize_render_path {
+      let chunk_dir = nodejs_path::dirname(&nodejs_path::resolve!(input_base, self.id.as_str()));
+      let import_path = nodejs_path::relative(&chunk_dir, &external_module.id);
+      if import_path.starts_with("../") {
+        import_path
+      } else {
+        format!("./{}", import_path)
+      }
+    } else {
+      external_module.id.clone()
+    }
+  }
+
   pub fn get_chunk_info_with_file_names(&self) -> OutputChunk {
     OutputChunk {
       code: "".to_string(),
@@ -0,0 +1,398 @@
+use std::collections::{HashMap, HashSet};
+
+use swc_atoms::JsWord;
+use swc_common::{comments::SingleThreadedComments, util::take::Take, Mark, SyntaxContext};
+use swc_ecma_ast::{
+  ArrowExpr, AssignPatProp, BinExpr, BinaryOp, BindingIdent, Bool, ClassDecl, Decl, EmptyStmt,
+  ExportDecl, Expr, FnDecl, Function, Ident, Lit, MemberExpr, ModuleDecl, ModuleItem, ParenExpr,
+  Pat, PatOrExpr, Stmt, UnaryExpr, UnaryOp, UpdateExpr, VarDecl, VarDeclKind, VarDeclarator,
+};
+use swc_ecma_visit::{
+  noop_visit_mut_type, noop_visit_type, Visit, VisitMut, VisitMutWith, VisitWith,
+};
+
+use crate::{
+  ext::SyntaxContextExt, module::Module, scanner::helper::static_member_prop, statement::Statement,
+  symbol_box::SymbolBox, types::NormalizedTreeshakingOptions,
+  utils::side_effect::detect_side_effect,
+};
+
+// Values of top-level `const` bindings initialized with literals, keyed by root marks. Imports share
+// the root mark with the declaration, so `if (DEBUG)` is folded in importing modules as well. `var`
+// bindings are folded as well if they are never written and they are assumed to be read after their
+// declarations.
+pub struct Constants {
+  values: HashMap<Mark, Lit>,
+}
+
+impl Constants {
+  pub fn new<'a>(
+    modules: impl Iterator<Item = &'a Module>,
+    symbol_box: &mut SymbolBox,
+    options: &NormalizedTreeshakingOptions,
+  ) -> Self {
+    let statements = modules
+      .flat_map(|module| module.statements.iter())
+      .collect::<Vec<_>>();
+    let mut counter = BindingCounter::default();
+    statements
+      .iter()
+      .for_each(|stmt| stmt.node.visit_with(&mut counter));
+    let values = statements
+      .iter()
+      .filter_map(|stmt| top_level_var_decl(&stmt.node))
+      .filter(|var_decl| match var_decl.kind {
+        VarDeclKind::Const => true,
+        // `var` bindings are `undefined` until their declarations run.
+        VarDeclKind::Var => !options.correct_var_value_before_declaration,
+        VarDeclKind::Let => false,
+      })
+      .flat_map(|var_decl| {
+        var_decl
+          .decls
+          .iter()
+          .map(move |declarator| (var_decl.kind, declarator))
+      })
+      .filter_map(
+        |(kind, declarator)| match (&declarator.name, declarator.init.as_deref()) {
+          (Pat::Ident(BindingIdent { id, .. }), Some(Expr::Lit(lit)))
+            if is_primitive(lit) && (kind == VarDeclKind::Const || counter.is_bound_once(id)) =>
+          {
+            Some((symbol_box.find_root(id.span.ctxt.as_mark()), lit.clone()))
+          }
+          _ => None,
+        },
+      )
+      .collect();
+    Self { values }
+  }
+
+  fn eval(&self, symbol_box: &mut SymbolBox, expr: &Expr) -> Option<Lit> {
+    match expr {
+      Expr::Lit(lit) if is_primitive(lit) => Some(lit.clone()),
+      Expr::Ident(ident) if ident.span.ctxt != SyntaxContext::empty() => self
+        .values
+        .get(&symbol_box.find_root(ident.span.ctxt.as_mark()))
+        .cloned(),
+      Expr::Paren(ParenExpr { expr, .. }) => self.eval(symbol_box, expr),
+      Expr::Unary(UnaryExpr {
+        op: UnaryOp::Bang,
+        arg,
+        span,
+      }) => Some(Lit::Bool(Bool {
+        span: *span,
+        value: !is_truthy(&self.eval(symbol_box, arg)?),
+      })),
+      Expr::Bin(BinExpr {
+        op: op @ (BinaryOp::EqEqEq | BinaryOp::NotEqEq),
+        left,
+        right,
+        span,
+      }) => {
+        let equals = strict_equals(
+          &self.eval(symbol_box, left)?,
+          &self.eval(symbol_box, right)?,
+        );
+        Some(Lit::Bool(Bool {
+          span: *span,
+          value: equals == (*op == BinaryOp::EqEqEq),
+        }))
+      }
+      _ => None,
+    }
+  }
+
+  // Folds the statement, and analyzes it again if anything is dropped. Folding only removes code, so
+  // a statement without side effects stays so.
+  pub fn fold_statement(
+    &self,
+    symbol_box: &mut SymbolBox,
+    stmt: &mut Statement,
+    root_marks: &HashSet<Mark>,
+    options: &NormalizedTreeshakingOptions,
+  ) {
+    let mut folder = ConstantFolder {
+      constants: self,
+      symbol_box,
+      changed: false,
+    };
+    stmt.node.visit_mut_with(&mut folder);
+    if !folder.changed {
+      return;
+    }
+
+    let mut collector = ReadsCollector {
+      root_marks,
+      reads: Default::default(),
+      member_reads: Default::default(),
+    };
+    stmt.node.visit_with(&mut collector);
+    stmt.reads = collector.reads;
+    stmt.member_reads = collector.member_reads;
+    if stmt.side_effect.is_some() {
+      // Comments are gone after scanning, so `__PURE__` annotations of the statement are ignored.
+      let mut callees = HashSet::new();
+      stmt.side_effect = detect_side_effect(
+        &stmt.node,
+        options,
+        &SingleThreadedComments::default(),
+        root_marks,
+        &mut callees,
+      );
+      stmt.callees = callees;
+    }
+  }
+}
+
+fn top_level_var_decl(item: &ModuleItem) -> Option<&VarDecl> {
+  match item {
+    ModuleItem::Stmt(Stmt::Decl(Decl::Var(var_decl)))
+    | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+      decl: Decl::Var(var_decl),
+      ..
+    })) => Some(var_decl),
+    _ => None,
+  }
+}
+
+fn is_primitive(lit: &Lit) -> bool {
+  matches!(lit, Lit::Bool(_) | Lit::Num(_) | Lit::Str(_) | Lit::Null(_))
+}
+
+fn is_truthy(lit: &Lit) -> bool {
+  match lit {
+    Lit::Bool(bool) => bool.value,
+    Lit::Num(num) => num.value != 0.0 && !num.value.is_nan(),
+    Lit::Str(str) => !str.value.is_empty(),
+    _ => false,
+  }
+}
+
+fn strict_equals(left: &Lit, right: &Lit) -> bool {
+  match (left, right) {
+    (Lit::Bool(left), Lit::Bool(right)) => left.value == right.value,
+    (Lit::Num(left), Lit::Num(right)) => left.value == right.value,
+    (Lit::Str(left), Lit::Str(right)) => left.value == right.value,
+    (Lit::Null(_), Lit::Null(_)) => true,
+    _ => false,
+  }
+}
+
+// Counts declarations of and writes to each binding. A `var` binding counted once is only
+// initialized by its declaration.
+#[derive(Default)]
+struct BindingCounter {
+  counts: HashMap<Mark, usize>,
+}
+
+impl BindingCounter {
+  fn count(&mut self, ident: &Ident) {
+    if ident.span.ctxt != SyntaxContext::empty() {
+      *self.counts.entry(ident.span.ctxt.as_mark()).or_default() += 1;
+    }
+  }
+
+  fn count_expr(&mut self, expr: &Expr) {
+    if let Expr::Ident(ident) = expr {
+      self.count(ident);
+    }
+  }
+
+  fn is_bound_once(&self, ident: &Ident) -> bool {
+    self.counts.get(&ident.span.ctxt.as_mark()) == Some(&1)
+  }
+}
+
+impl Visit for BindingCounter {
+  noop_visit_type!();
+
+  // `var foo = 1`, `[foo] = []` and `for (foo of [])`
+  fn visit_pat(&mut self, pat: &Pat) {
+    match pat {
+      Pat::Ident(binding) => self.count(&binding.id),
+      Pat::Expr(expr) => self.count_expr(expr.as_ref()),
+      _ => {}
+    }
+    pat.visit_children_with(self);
+  }
+
+  // `({ foo } = {})`
+  fn visit_assign_pat_prop(&mut self, prop: &AssignPatProp) {
+    self.count(&prop.key);
+    prop.visit_children_with(self);
+  }
+
+  // `foo = 1`
+  fn visit_pat_or_expr(&mut self, target: &PatOrExpr) {
+    if let PatOrExpr::Expr(expr) = target {
+      self.count_expr(expr.as_ref());
+    }
+    target.visit_children_with(self);
+  }
+
+  // `foo++`
+  fn visit_update_expr(&mut self, update_expr: &UpdateExpr) {
+    self.count_expr(update_expr.arg.as_ref());
+    update_expr.visit_children_with(self);
+  }
+
+  // `function foo() {}` declares `var foo` again.
+  fn visit_fn_decl(&mut self, fn_decl: &FnDecl) {
+    self.count(&fn_decl.ident);
+    fn_decl.visit_children_with(self);
+  }
+}
+
+// `var` declarations are hoisted out of the branch, so the branch can't be dropped entirely.
+#[derive(Default)]
+struct VarFinder {
+  found: bool,
+}
+
+impl Visit for VarFinder {
+  noop_visit_type!();
+
+  fn visit_var_decl(&mut self, var_decl: &VarDecl) {
+    self.found |= var_decl.kind == VarDeclKind::Var;
+    var_decl.visit_children_with(self);
+  }
+
+  fn visit_function(&mut self, _: &Function) {}
+
+  fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+}
+
+fn declares_var(stmt: &Stmt) -> bool {
+  let mut finder = VarFinder::default();
+  stmt.visit_with(&mut finder);
+  finder.found
+}
+
+struct ConstantFolder<'a> {
+  constants: &'a Constants,
+  symbol_box: &'a mut SymbolBox,
+  changed: bool,
+}
+
+impl<'a> ConstantFolder<'a> {
+  fn eval_truthy(&mut self, expr: &Expr) -> Option<bool> {
+    self
+      .constants
+      .eval(self.symbol_box, expr)
+      .map(|lit| is_truthy(&lit))
+  }
+}
+
+impl<'a> VisitMut for ConstantFolder<'a> {
+  noop_visit_mut_type!();
+
+  fn visit_mut_stmt(&mut self, stmt: &mut Stmt) {
+    stmt.visit_mut_children_with(self);
+    let folded = match stmt {
+      Stmt::If(if_stmt) => match self.eval_truthy(&if_stmt.test) {
+        Some(true) if !if_stmt.alt.as_deref().map_or(false, declares_var) => {
+          Some(Stmt::take(&mut if_stmt.cons))
+        }
+        Some(false) if !declares_var(&if_stmt.cons) => Some(match if_stmt.alt.as_mut() {
+          Some(alt) => Stmt::take(alt),
+          None => Stmt::Empty(EmptyStmt { span: if_stmt.span }),
+        }),
+        _ => None,
+      },
+      _ => None,
+    };
+    if let Some(folded) = folded {
+      *stmt = folded;
+      self.changed = true;
+    }
+  }
+
+  fn visit_mut_expr(&mut self, expr: &mut Expr) {
+    expr.visit_mut_children_with(self);
+    let folded = match expr {
+      Expr::Cond(cond) => self.eval_truthy(&cond.test).map(|test| {
+        if test {
+          Expr::take(&mut cond.cons)
+        } else {
+          Expr::take(&mut cond.alt)
+        }
+      }),
+      // `a && b` is `a` if `a` is falsy, and `a || b` is `a` if `a` is truthy.
+      Expr::Bin(BinExpr {
+        op: op @ (BinaryOp::LogicalAnd | BinaryOp::LogicalOr),
+        left,
+        right,
+        ..
+      }) => self.constants.eval(self.symbol_box, left).map(|lit| {
+        if is_truthy(&lit) == (*op == BinaryOp::LogicalAnd) {
+          Expr::take(right)
+        } else {
+          Expr::Lit(lit)
+        }
+      }),
+      Expr::Bin(BinExpr {
+        op: BinaryOp::NullishCoalescing,
+        left,
+        right,
+        ..
+      }) => self
+        .constants
+        .eval(self.symbol_box, left)
+        .map(|lit| match lit {
+          Lit::Null(_) => Expr::take(right),
+          lit => Expr::Lit(lit),
+        }),
+      _ => None,
+    };
+    if let Some(folded) = folded {
+      *expr = folded;
+      self.changed = true;
+    }
+  }
+}
+
+// Collects reads of top-level bindings the same way as the scanner does.
+struct ReadsCollector<'a> {
+  root_marks: &'a HashSet<Mark>,
+  reads: HashSet<Mark>,
+  member_reads: HashSet<(Mark, JsWord)>,
+}
+
+impl<'a> Visit for ReadsCollector<'a> {
+  noop_visit_type!();
+
+  fn visit_ident(&mut self, ident: &Ident) {
+    let mark = ident.span.ctxt.as_mark();
+    if self.root_marks.contains(&mark) {
+      self.reads.insert(mark);
+    }
+  }
+
+  fn visit_member_expr(&mut self, expr: &MemberExpr) {
+    match (expr.obj.as_ref(), static_member_prop(&expr.prop)) {
+      (Expr::Ident(obj), Some(prop)) if self.root_marks.contains(&obj.span.ctxt.as_mark()) => {
+        self.member_reads.insert((obj.span.ctxt.as_mark(), prop));
+      }
+      _ => expr.obj.visit_with(self),
+    }
+    if expr.prop.is_computed() {
+      expr.prop.visit_with(self);
+    }
+  }
+
+  // Declared names aren't reads. Patterns are visited for their default values.
+  fn visit_var_declarator(&mut self, declarator: &VarDeclarator) {
+    if !matches!(declarator.name, Pat::Ident(_)) {
+      declarator.name.visit_with(self);
+    }
+    declarator.init.visit_with(self);
+  }
+
+  fn visit_fn_decl(&mut self, fn_decl: &FnDecl) {
+    fn_decl.function.visit_with(self);
+  }
+
+  fn visit_class_decl(&mut self, class_decl: &ClassDecl) {
+    class_decl.class.visit_with(self);
+  }
+}
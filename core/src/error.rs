@@ -0,0 +1,202 @@
+use std::fmt;
+
+use swc_common::{FileName, Span};
+use thiserror::Error;
+
+use crate::{compiler::SOURCE_MAP, utils::path::relative_id};
+
+// Align to https://github.com/rollup/rollup/blob/master/src/utils/error.ts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+  MissingExport,
+  MissingOption,
+  ParseError,
+  UndefinedExport,
+  UnresolvedEntry,
+  UnresolvedImport,
+  UnsupportedSyntax,
+}
+
+impl ErrorCode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::MissingExport => "MISSING_EXPORT",
+      Self::MissingOption => "MISSING_OPTION",
+      Self::ParseError => "PARSE_ERROR",
+      Self::UndefinedExport => "UNDEFINED_EXPORT",
+      Self::UnresolvedEntry => "UNRESOLVED_ENTRY",
+      Self::UnresolvedImport => "UNRESOLVED_IMPORT",
+      Self::UnsupportedSyntax => "UNSUPPORTED_SYNTAX",
+    }
+  }
+}
+
+impl fmt::Display for ErrorCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+  pub file: String,
+  // 1-based
+  pub line: usize,
+  // 0-based
+  pub column: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildError {
+  pub code: ErrorCode,
+  pub message: String,
+  // The module where the error happens.
+  pub id: Option<String>,
+  pub loc: Option<Location>,
+  pub frame: Option<String>,
+}
+
+impl BuildError {
+  pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+      id: None,
+      loc: None,
+      frame: None,
+    }
+  }
+
+  pub fn with_id(mut self, id: impl Into<String>) -> Self {
+    self.id = Some(id.into());
+    self
+  }
+
+  // Fill `loc` and `frame` with the source file registered in `SOURCE_MAP`.
+  pub fn with_span(mut self, span: Span) -> Self {
+    if let Some((loc, frame)) = locate(span) {
+      self.id.get_or_insert_with(|| loc.file.clone());
+      self.loc = Some(loc);
+      self.frame = Some(frame);
+    }
+    self
+  }
+}
+
+impl fmt::Display for BuildError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "[{}] {}", self.code, self.message)?;
+    fmt_position(f, &self.id, &self.loc, &self.frame)
+  }
+}
+
+impl std::error::Error for BuildError {}
+
+#[derive(Error, Debug)]
+pub enum RolldownError {
+  #[error("[IO error `{0}`]")]
+  IO(std::io::Error),
+  // The message is dropped, since `Msg` may carry a `RolldownError` itself.
+  #[error("[Crossbeam error `sending on a disconnected channel`]")]
+  Channel,
+  #[error(transparent)]
+  Build(#[from] BuildError),
+  #[error("{}", join_errors(.0))]
+  Errors(Vec<BuildError>),
+  // A worker panicked while processing the module.
+  #[error("[Worker panicked `{1}` while processing {0}]")]
+  Panic(String, String),
+}
+
+fn join_errors(errors: &[BuildError]) -> String {
+  errors
+    .iter()
+    .map(|err| err.to_string())
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+// The location and the code frame of `span`.
+pub(crate) fn locate(span: Span) -> Option<(Location, String)> {
+  if span.is_dummy() {
+    return None;
+  }
+  let loc = SOURCE_MAP.lookup_char_pos(span.lo);
+  let file = match &loc.file.name {
+    FileName::Custom(id) => id.clone(),
+    name => name.to_string(),
+  };
+  let frame = get_code_frame(&loc.file.src, loc.line, loc.col.0);
+  Some((
+    Location {
+      file,
+      line: loc.line,
+      column: loc.col.0,
+    },
+    frame,
+  ))
+}
+
+pub(crate) fn fmt_position(
+  f: &mut fmt::Formatter<'_>,
+  id: &Option<String>,
+  loc: &Option<Location>,
+  frame: &Option<String>,
+) -> fmt::Result {
+  match (loc, id) {
+    (Some(loc), _) => write!(
+      f,
+      "\n  at {}:{}:{}",
+      relative_id(loc.file.clone()),
+      loc.line,
+      loc.column
+    )?,
+    (None, Some(id)) => write!(f, "\n  at {}", relative_id(id.clone()))?,
+    (None, None) => {}
+  }
+  if let Some(frame) = frame {
+    write!(f, "\n\n{}", frame)?;
+  }
+  Ok(())
+}
+
+// Such as
+// 1: import { foo } from './foo'
+// 2: console.log(foo;
+//                   ^
+// 3: export default foo
+pub fn get_code_frame(source: &str, line: usize, column: usize) -> String {
+  let lines = source.lines().collect::<Vec<_>>();
+  let frame_start = line.saturating_sub(3);
+  let frame_end = (line + 2).min(lines.len());
+  let frame_start = frame_start.min(frame_end);
+  let gutter_width = frame_end.to_string().len();
+
+  lines[frame_start..frame_end]
+    .iter()
+    .enumerate()
+    .map(|(index, content)| {
+      let line_number = frame_start + index + 1;
+      let mut frame_line = format!(
+        "{:>width$}: {}",
+        line_number,
+        content.replace('\t', "  "),
+        width = gutter_width
+      );
+      if line_number == line {
+        let indentation = content
+          .chars()
+          .take(column)
+          .map(|c| if c == '\t' { "  " } else { " " })
+          .collect::<String>();
+        frame_line.push_str(&format!(
+          "\n{}{}^",
+          " ".repeat(gutter_width + 2),
+          indentation
+        ));
+      }
+      frame_line
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
@@ -3,9 +3,7 @@ use std::{
   path::{Path, PathBuf},
 };
 
-use swc_common::{Mark, SyntaxContext, GLOBALS};
-
-use crate::symbol_box::SYMBOL_GLOBALS;
+use swc_common::{Mark, SyntaxContext};
 
 pub trait PathExt {
   fn as_str(&self) -> Cow<'_, str>;
@@ -29,6 +27,8 @@ impl StrExt for str {
   }
 }
 
+// Marks are never applied through swc's hygiene data, which is shared by all threads behind a lock.
+// The syntax context of an identifier is its mark itself, so converting between them is free.
 pub trait MarkExt {
   fn as_ctxt(&self) -> SyntaxContext;
 }
@@ -36,7 +36,7 @@ pub trait MarkExt {
 impl MarkExt for Mark {
   #[inline]
   fn as_ctxt(&self) -> SyntaxContext {
-    GLOBALS.set(&SYMBOL_GLOBALS, || SyntaxContext::empty().apply_mark(*self))
+    SyntaxContext::from_u32(self.as_u32())
   }
 }
 
@@ -47,6 +47,6 @@ pub trait SyntaxContextExt {
 impl SyntaxContextExt for SyntaxContext {
   #[inline]
   fn as_mark(&self) -> Mark {
-    GLOBALS.set(&SYMBOL_GLOBALS, || self.outer())
+    Mark::from_u32(self.as_u32())
   }
 }
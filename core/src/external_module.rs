@@ -6,12 +6,15 @@ use std::hash::Hash;
 pub struct ExternalModule {
   pub id: String,
   pub module_side_effects: bool,
+  // Whether the absolute `id` should be rendered relative to the output chunk.
+  pub renormalize_render_path: bool,
 }
 impl ExternalModule {
-  pub fn new(id: String) -> Self {
+  pub fn new(id: String, renormalize_render_path: bool) -> Self {
     ExternalModule {
       id,
       module_side_effects: true,
+      renormalize_render_path,
     }
   }
 }
@@ -1,42 +1,51 @@
 use std::{
+  any::Any,
   collections::{HashMap, HashSet},
-  sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc, Mutex,
-  },
+  panic::{self, AssertUnwindSafe},
+  sync::{Arc, Mutex},
 };
 
-use crossbeam::{
-  channel::{self},
-  queue::SegQueue,
-};
-use dashmap::{DashMap, DashSet};
+use crossbeam::channel::{self};
+use dashmap::DashMap;
 use petgraph::{graph::NodeIndex, visit::EdgeRef, EdgeDirection};
 use rayon::prelude::*;
 use smol_str::SmolStr;
 
+use swc_atoms::JsWord;
 use swc_common::Mark;
+use swc_ecma_visit::VisitMutWith;
 
 use crate::{
+  constants::Constants,
+  error::{BuildError, ErrorCode, RolldownError},
   external_module::ExternalModule,
+  members::MemberUsage,
   module::Module,
+  namespace::{NamespaceMemberRewriter, NamespaceResolver},
+  plugin_driver::PluginDriver,
   scanner::rel::RelationInfo,
-  symbol_box::SymbolBox,
+  symbol_box::{renumber_mark, SymbolBox},
   types::{NormalizedInputOptions, ResolvedId},
-  utils::{is_decl_or_stmt, resolve_id},
+  utils::{
+    is_decl_or_stmt, package_json::PackageJsonCache, path::relative_id, resolve_id,
+    side_effect::SideEffect,
+  },
+  warning::{Warning, WarningCode},
   worker::Worker,
 };
 
 type ModulePetGraph = petgraph::graph::DiGraph<SmolStr, Rel>;
 
 pub struct Graph {
-  pub input_options: NormalizedInputOptions,
+  pub input_options: Arc<NormalizedInputOptions>,
+  pub plugin_driver: Arc<PluginDriver>,
   resolved_entries: Vec<ResolvedId>,
   pub module_graph: ModulePetGraph,
   pub entry_indexs: Vec<NodeIndex>,
   pub ordered_modules: Vec<NodeIndex>,
   pub symbol_box: Arc<Mutex<SymbolBox>>,
   pub module_by_id: HashMap<SmolStr, Box<Module>>,
+  pub external_by_id: HashMap<SmolStr, ExternalModule>,
   pub mark_to_stmt: Arc<DashMap<Mark, (SmolStr, usize)>>,
 }
 
@@ -60,19 +69,29 @@ impl Rel {
 }
 
 pub enum Msg {
+  // A module to be scanned. Ids which are already scheduled are ignored.
+  NewJob(ResolvedId),
+  // A scheduled job is done, whether it succeeded or not.
+  JobDone,
   DependencyReference(SmolStr, SmolStr, Rel),
   NewMod(Box<Module>),
   NewExtMod(ExternalModule),
+  Warning(Warning),
+  Errors(Vec<BuildError>),
+  Error(RolldownError),
 }
 
 impl Graph {
-  pub fn new(input_options: NormalizedInputOptions) -> Self {
+  pub fn new(mut input_options: NormalizedInputOptions) -> Self {
+    let plugin_driver = PluginDriver::from_plugins(std::mem::take(&mut input_options.plugins));
     Self {
-      input_options,
+      input_options: Arc::new(input_options),
+      plugin_driver: Arc::new(plugin_driver),
       resolved_entries: Default::default(),
       entry_indexs: Default::default(),
       ordered_modules: Default::default(),
       module_by_id: Default::default(),
+      external_by_id: Default::default(),
       module_graph: ModulePetGraph::new(),
       symbol_box: Arc::new(Mutex::new(SymbolBox::new())),
       mark_to_stmt: Default::default(),
@@ -86,80 +105,157 @@ impl Graph {
       ..Default::default()
     })
   }
+
+  pub fn warn(&self, warning: Warning) {
+    match &self.input_options.on_warn {
+      Some(on_warn) => on_warn(&warning),
+      None => log::warn!("{}", warning),
+    }
+  }
+
   // build dependency graph via entry modules.
-  fn generate_module_graph(&mut self) {
-    let nums_of_thread = num_cpus::get();
-    let idle_thread_count: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(nums_of_thread));
-    let job_queue: Arc<SegQueue<ResolvedId>> = Default::default();
+  fn generate_module_graph(&mut self) -> Result<(), RolldownError> {
     self.resolved_entries = self
       .input_options
       .input
       .iter()
-      .map(|entry| resolve_id(entry, None, false))
-      .collect();
+      .map(|entry| resolve_id(entry, None, &self.input_options, &self.plugin_driver))
+      .collect::<Result<_, _>>()?;
 
     let mut path_to_node_idx: HashMap<SmolStr, NodeIndex> = Default::default();
 
+    // Jobs are only scheduled by the main thread. Workers block on `job_rx` until there's something to
+    // do and exit once `job_tx` is dropped.
+    let (job_tx, job_rx) = channel::unbounded::<ResolvedId>();
+    let (tx, rx) = channel::unbounded::<Msg>();
+    let mut processed_id: HashSet<SmolStr> = Default::default();
+    // Number of scheduled jobs which haven't been done yet. The module graph is completed when it's `0`.
+    let mut pending_jobs = 0;
+
     self.resolved_entries.iter().for_each(|resolved_entry_id| {
       let entry_idx = self.module_graph.add_node(resolved_entry_id.id.clone());
       self.entry_indexs.push(entry_idx);
       path_to_node_idx.insert(resolved_entry_id.id.clone(), entry_idx);
-      job_queue.push(resolved_entry_id.clone());
+      if processed_id.insert(resolved_entry_id.id.clone()) {
+        pending_jobs += 1;
+        job_tx.send(resolved_entry_id.clone()).ok();
+      }
     });
 
-    let processed_id: Arc<DashSet<SmolStr>> = Default::default();
-
-    let (tx, rx) = channel::unbounded::<Msg>();
-
+    let nums_of_thread = self
+      .input_options
+      .max_parallel_file_reads
+      .unwrap_or_else(num_cpus::get)
+      .max(1);
+    let package_json_cache = Arc::new(PackageJsonCache::default());
     for _ in 0..nums_of_thread {
-      let idle_thread_count = idle_thread_count.clone();
+      let job_rx = job_rx.clone();
       let mut worker = Worker {
         tx: tx.clone(),
-        job_queue: job_queue.clone(),
-        processed_id: processed_id.clone(),
-        symbol_box: self.symbol_box.clone(),
-        mark_to_stmt: self.mark_to_stmt.clone(),
+        input_options: self.input_options.clone(),
+        plugin_driver: self.plugin_driver.clone(),
+        package_json_cache: package_json_cache.clone(),
       };
-      std::thread::spawn(move || loop {
-        idle_thread_count.fetch_sub(1, Ordering::SeqCst);
-        if let Err(e) = worker.run() {
-          eprintln!("{}", e);
-        }
-        idle_thread_count.fetch_add(1, Ordering::SeqCst);
-        loop {
-          if !worker.job_queue.is_empty() {
-            break;
-            // need to work again
-          } else if idle_thread_count.load(Ordering::SeqCst) == nums_of_thread {
-            // All threads are idle now. There's no more work to do.
-            return;
+      std::thread::spawn(move || {
+        while let Ok(resolved_id) = job_rx.recv() {
+          let id = resolved_id.id.to_string();
+          // A panicking job is still done. Otherwise `rx.recv()` would wait for it forever.
+          let result = panic::catch_unwind(AssertUnwindSafe(|| worker.run(resolved_id)))
+            .unwrap_or_else(|payload| Err(RolldownError::Panic(id, panic_message(payload))));
+          if let Err(e) = result {
+            worker.tx.send(Msg::Error(e)).ok();
           }
+          worker.tx.send(Msg::JobDone).ok();
         }
       });
     }
+    // Otherwise `rx.recv()` would block forever if all workers are gone.
+    drop(tx);
 
-    while idle_thread_count.load(Ordering::SeqCst) != nums_of_thread
-      || job_queue.len() > 0
-      || !rx.is_empty()
-    {
-      if let Ok(job) = rx.try_recv() {
-        match job {
-          Msg::NewMod(module) => {
-            self.module_by_id.insert(module.id.clone(), module);
+    let mut errors = vec![];
+    let mut warnings = vec![];
+    let mut fatal_error = None;
+
+    while pending_jobs > 0 {
+      let msg = match rx.recv() {
+        Ok(msg) => msg,
+        Err(_) => break,
+      };
+      match msg {
+        Msg::NewJob(resolved_id) => {
+          // Stop scheduling further work after a fatal error.
+          if fatal_error.is_none() && processed_id.insert(resolved_id.id.clone()) {
+            pending_jobs += 1;
+            job_tx.send(resolved_id).ok();
           }
-          Msg::DependencyReference(from, to, rel) => {
-            let from_id = *path_to_node_idx
-              .entry(from)
-              .or_insert_with_key(|key| self.module_graph.add_node(key.clone()));
-            let to_id = *path_to_node_idx
-              .entry(to)
-              .or_insert_with_key(|key| self.module_graph.add_node(key.clone()));
-            self.module_graph.add_edge(from_id, to_id, rel);
+        }
+        Msg::JobDone => {
+          pending_jobs -= 1;
+        }
+        Msg::NewMod(module) => {
+          self.module_by_id.insert(module.id.clone(), module);
+        }
+        Msg::NewExtMod(external_module) => {
+          // Importers report the same external module in any order. Merge them so that the result
+          // doesn't depend on which one comes first.
+          self
+            .external_by_id
+            .entry(external_module.id.clone().into())
+            .and_modify(|existing| {
+              existing.module_side_effects |= external_module.module_side_effects;
+              existing.renormalize_render_path |= external_module.renormalize_render_path;
+            })
+            .or_insert(external_module);
+        }
+        Msg::Warning(warning) => {
+          warnings.push(warning);
+        }
+        Msg::Errors(mut module_errors) => {
+          errors.append(&mut module_errors);
+        }
+        Msg::Error(err) => {
+          // Only the first fatal error is reported. The others might be caused by it.
+          fatal_error.get_or_insert(err);
+          // Drop the jobs which haven't been picked up by workers.
+          while job_rx.try_recv().is_ok() {
+            pending_jobs -= 1;
           }
-          _ => {}
+        }
+        Msg::DependencyReference(from, to, rel) => {
+          let from_id = *path_to_node_idx
+            .entry(from)
+            .or_insert_with_key(|key| self.module_graph.add_node(key.clone()));
+          let to_id = *path_to_node_idx
+            .entry(to)
+            .or_insert_with_key(|key| self.module_graph.add_node(key.clone()));
+          self.module_graph.add_edge(from_id, to_id, rel);
         }
       }
     }
+    // Let idle workers exit.
+    drop(job_tx);
+
+    // Modules are scanned in parallel. Warnings of each module are kept in their order.
+    warnings.sort_by(|a, b| a.id.cmp(&b.id));
+    warnings.into_iter().for_each(|warning| self.warn(warning));
+
+    if let Some(err) = fatal_error {
+      return Err(err);
+    }
+
+    if !errors.is_empty() {
+      // Modules are scanned in parallel. Keep the report stable.
+      errors.sort_by(|a, b| {
+        let position = |err: &BuildError| {
+          (
+            err.id.clone(),
+            err.loc.as_ref().map(|loc| (loc.line, loc.column)),
+          )
+        };
+        position(a).cmp(&position(b))
+      });
+      return Err(RolldownError::Errors(errors));
+    }
 
     let entries_id = self
       .entry_indexs
@@ -169,152 +265,524 @@ impl Graph {
     self.module_by_id.par_iter_mut().for_each(|(_key, module)| {
       module.is_user_defined_entry_point = entries_id.contains(&module.id);
     });
+    Ok(())
   }
 
+  // Execution order of modules, following Rollup. Dependencies are executed before their importers in
+  // the order of imports, starting from each entry in input order. An import closing a cycle is
+  // skipped and reported.
   fn sort_modules(&mut self) {
     let mut ordered_modules = vec![];
-    let entry = self.entry_indexs[0];
-    let mut visited = HashSet::new();
-    let mut stack = vec![entry];
-    while let Some(node_idx) = stack.pop() {
-      if !visited.contains(&node_idx) {
-        stack.push(node_idx);
-        visited.insert(node_idx);
-        let edges = self
-          .module_graph
-          .edges_directed(node_idx, EdgeDirection::Outgoing);
-        let mut rels = edges.collect::<Vec<_>>();
-        rels.sort_by_key(|a| a.weight().get_order());
-        rels
-          .into_iter()
-          .rev()
-          .filter(|edge| !visited.contains(&edge.target()))
-          .for_each(|edge| stack.push(edge.target()));
-      } else {
-        ordered_modules.push(node_idx);
+    let mut cycle_paths = vec![];
+    // module -> the importer it's reached from first. `None` for entries.
+    let mut parents: HashMap<NodeIndex, Option<NodeIndex>> = HashMap::new();
+    let mut analysed_modules = HashSet::new();
+
+    for entry in self.entry_indexs.iter().copied() {
+      if parents.contains_key(&entry) {
+        continue;
+      }
+      parents.insert(entry, None);
+      // (module, dependencies in import order, index of the next dependency)
+      let mut stack = vec![(entry, self.dependencies_in_order(entry), 0)];
+      while let Some((node_idx, dependencies, next)) = stack.last_mut() {
+        let node_idx = *node_idx;
+        let dependency = dependencies.get(*next).copied();
+        *next += 1;
+        match dependency {
+          Some(dependency) if parents.contains_key(&dependency) => {
+            // The dependency is still being analysed, so it's an ancestor of the current module.
+            if !analysed_modules.contains(&dependency) {
+              cycle_paths.push(self.cycle_path(dependency, node_idx, &parents));
+            }
+          }
+          Some(dependency) => {
+            parents.insert(dependency, Some(node_idx));
+            stack.push((dependency, self.dependencies_in_order(dependency), 0));
+          }
+          None => {
+            stack.pop();
+            analysed_modules.insert(node_idx);
+            ordered_modules.push(node_idx);
+          }
+        }
       }
-      // ordered_modules = ordered_modules.into_iter().rev().collect()
     }
 
-    // while let Some(node) = dfs.next(&self.graph) {
-    //   ordered_modules.push(node);
-    // }
+    cycle_paths.into_iter().for_each(|path| {
+      self.warn(Warning::new(
+        WarningCode::CircularDependency,
+        format!("Circular dependency: {}", path.join(" -> ")),
+      ))
+    });
     self.ordered_modules = ordered_modules;
-    // println!("self.ordered_modules {:#?}", ordered_modules.iter().map(|idx| &self.graph[*idx]).collect::<Vec<_>>());
   }
 
-  pub fn build(&mut self) {
-    self.generate_module_graph();
+  fn dependencies_in_order(&self, node_idx: NodeIndex) -> Vec<NodeIndex> {
+    let mut edges = self
+      .module_graph
+      .edges_directed(node_idx, EdgeDirection::Outgoing)
+      .collect::<Vec<_>>();
+    edges.sort_by_key(|edge| edge.weight().get_order());
+    let mut visited = HashSet::new();
+    edges
+      .into_iter()
+      .map(|edge| edge.target())
+      .filter(|target| visited.insert(*target))
+      .collect()
+  }
+
+  // `a -> b -> c -> a`, where `c` imports `a`.
+  fn cycle_path(
+    &self,
+    module: NodeIndex,
+    importer: NodeIndex,
+    parents: &HashMap<NodeIndex, Option<NodeIndex>>,
+  ) -> Vec<String> {
+    let mut path = vec![relative_id(self.module_graph[module].to_string())];
+    let mut next = importer;
+    while next != module {
+      path.push(relative_id(self.module_graph[next].to_string()));
+      next = parents[&next].expect("modules in a cycle have importers");
+    }
+    path.push(path[0].clone());
+    path.reverse();
+    path
+  }
+
+  pub fn build(&mut self) -> Result<(), RolldownError> {
+    self.generate_module_graph()?;
     self.sort_modules();
+    self.renumber_marks();
+    self.bind_local_references()?;
     self.link_module_exports();
-    self.link_module();
+    self.link_module()?;
+    if self.input_options.treeshake.is_some() {
+      self.fold_constants();
+    }
     self.include();
+    if self.input_options.treeshake.is_some() {
+      self.remove_unused_members();
+    }
+    self.rewrite_namespace_members();
+    self.warn_unused_external_imports();
+    Ok(())
+  }
+
+  // Marks are allocated per module while scanning. Number them in the execution order, so that names
+  // in the output don't depend on thread scheduling.
+  fn renumber_marks(&mut self) {
+    let mut offset = 0;
+    self.ordered_modules.iter().for_each(|idx| {
+      let module = match self.module_by_id.get_mut(&self.module_graph[*idx]) {
+        Some(module) => module,
+        None => return,
+      };
+      module.renumber_marks(offset);
+      // Specifiers of imports and re-exports are declared by the importer.
+      let edges = self
+        .module_graph
+        .edges_directed(*idx, EdgeDirection::Outgoing)
+        .map(|edge| edge.id())
+        .collect::<Vec<_>>();
+      edges.into_iter().for_each(|edge| {
+        if let Rel::Import(info) | Rel::ReExport(info) = &mut self.module_graph[edge] {
+          info.names = info
+            .names
+            .drain()
+            .map(|mut specifier| {
+              specifier.mark = renumber_mark(specifier.mark, offset);
+              specifier
+            })
+            .collect();
+        }
+      });
+      module.stmt_marks.iter().for_each(|(mark, stmt_idx)| {
+        self
+          .mark_to_stmt
+          .entry(*mark)
+          .or_insert_with(|| (module.id.clone(), *stmt_idx));
+      });
+      offset += module.mark_count;
+    });
+    symbol_box_mut(&mut self.symbol_box).reserve(offset + 1);
+  }
+
+  fn bind_local_references(&mut self) -> Result<(), BuildError> {
+    let symbol_box = symbol_box_mut(&mut self.symbol_box);
+    self.ordered_modules.iter().try_for_each(|idx| {
+      match self.module_by_id.get(&self.module_graph[*idx]) {
+        Some(module) => module.bind_local_references(symbol_box),
+        None => Ok(()),
+      }
+    })
+  }
+
+  fn warn_unused_external_imports(&self) {
+    let mut symbol_box = self.symbol_box.lock().unwrap();
+    let read_marks = self
+      .module_by_id
+      .values()
+      .flat_map(|module| {
+        module
+          .statements
+          .iter()
+          .filter(|stmt| stmt.included)
+          .flat_map(|stmt| {
+            stmt
+              .reads
+              .iter()
+              .chain(stmt.member_reads.iter().map(|(obj, _)| obj))
+          })
+      })
+      .map(|mark| symbol_box.find_root(*mark))
+      .collect::<HashSet<_>>();
+
+    self.ordered_modules.iter().for_each(|idx| {
+      let importer = &self.module_graph[*idx];
+      let mut edges = self
+        .module_graph
+        .edges_directed(*idx, EdgeDirection::Outgoing)
+        .collect::<Vec<_>>();
+      edges.sort_by_key(|edge| edge.weight().get_order());
+      edges.into_iter().for_each(|edge| {
+        let external_id = &self.module_graph[edge.target()];
+        if !self.external_by_id.contains_key(external_id) {
+          return;
+        }
+        let info = match edge.weight() {
+          Rel::Import(info) => info,
+          _ => return,
+        };
+        let mut unused = info
+          .names
+          .iter()
+          .filter(|specifier| !read_marks.contains(&symbol_box.find_root(specifier.mark)))
+          .map(|specifier| format!("\"{}\"", specifier.original))
+          .collect::<Vec<_>>();
+        unused.sort();
+        if !unused.is_empty() {
+          self.warn(
+            Warning::new(
+              WarningCode::UnusedExternalImport,
+              format!(
+                "{} imported from external module \"{}\" but never used in \"{}\"",
+                unused.join(", "),
+                external_id,
+                relative_id(importer.to_string()),
+              ),
+            )
+            .with_id(importer.as_str()),
+          );
+        }
+      });
+    });
   }
 
   pub fn include(&mut self) {
-    let treeshake = self.input_options.treeshake;
+    let treeshake = self.input_options.treeshake.is_some();
     log::debug!("mark to stmt {:#?}", self.mark_to_stmt);
+    if treeshake {
+      self.resolve_no_side_effect_calls();
+    }
     self.module_by_id.par_iter_mut().for_each(|(id, module)| {
       log::debug!(
         "[treeshake]: with treeshake: {:?}, include all module's side effect stmt for {:?}",
         treeshake,
         id
       );
-      module.include(treeshake);
+      // Side effects of modules without side effects are only needed once they are used.
+      if !treeshake || module.module_side_effects || module.is_user_defined_entry_point {
+        module.include(treeshake);
+      }
     });
 
     if treeshake {
-      self.resolved_entries.iter().for_each(|resolved_id| {
-        log::debug!(
-          "[treeshake]: include entry module's local exports for {:?}",
-          resolved_id.id
-        );
-        let module = self.module_by_id.get_mut(&resolved_id.id.clone()).unwrap();
-        module
-          .local_exports
-          .values()
-          .map(|desc| (desc.local_name.clone(), desc.mark))
-          .collect::<Vec<_>>()
-          .into_iter()
-          .for_each(|(name, mark)| {
-            module.include_mark(&name, &mark);
-          });
+      let symbol_box = symbol_box_mut(&mut self.symbol_box);
+      let resolver = NamespaceResolver::new(
+        self.module_by_id.values().map(|module| module.as_ref()),
+        symbol_box,
+      );
+
+      // Statements declaring each root mark. It's built once so that resolving a read doesn't need to
+      // scan all declarations.
+      let mut root_to_stmts: HashMap<Mark, Vec<(SmolStr, usize)>> = Default::default();
+      self.mark_to_stmt.iter().for_each(|pair| {
+        let (module_id, idx) = pair.value();
+        // Skip declarations brought by `import`
+        if is_decl_or_stmt(&self.module_by_id[module_id].statements[*idx].node) {
+          root_to_stmts
+            .entry(symbol_box.find_root(*pair.key()))
+            .or_default()
+            .push((module_id.clone(), *idx));
+        }
       });
 
-      #[allow(clippy::needless_collect)]
-      let read_marks = self
-        .module_by_id
+      // Start from exports of entry modules and reads of statements which are already included.
+      let mut pending_marks = self
+        .resolved_entries
         .iter()
-        .flat_map(|(_id, module)| module.statements.iter().flat_map(|stmt| stmt.reads.iter()))
-        .cloned()
+        .flat_map(|resolved_id| {
+          log::debug!(
+            "[treeshake]: include entry module's exports for {:?}",
+            resolved_id.id
+          );
+          // Including re-exports and `export *`, which are linked by `link_module_exports`.
+          self.module_by_id[&resolved_id.id].exports.values().copied()
+        })
         .collect::<Vec<_>>();
+      self
+        .module_by_id
+        .values()
+        .flat_map(|module| module.statements.iter().filter(|stmt| stmt.included))
+        .for_each(|stmt| pending_marks.extend(resolver.reads_of(symbol_box, stmt)));
+      let mut visited_roots = HashSet::new();
+      let mut used_modules: HashSet<SmolStr> = HashSet::new();
+
+      // Each root mark is resolved only once. Newly included statements bring their reads in.
+      while let Some(mark) = pending_marks.pop() {
+        let root = symbol_box.find_root(mark);
+        if !visited_roots.insert(root) {
+          continue;
+        }
+        if let Some(stmts) = root_to_stmts.get(&root) {
+          stmts.iter().for_each(|(module_id, idx)| {
+            let module = self.module_by_id.get_mut(module_id).unwrap();
+            if !module.module_side_effects && used_modules.insert(module_id.clone()) {
+              module
+                .statements
+                .iter_mut()
+                .filter(|stmt| !stmt.included && stmt.side_effect.is_some())
+                .for_each(|stmt| {
+                  stmt.include();
+                  pending_marks.extend(resolver.reads_of(symbol_box, stmt));
+                });
+            }
+            let stmt = &mut module.statements[*idx];
+            if !stmt.included {
+              log::debug!(
+                "[treeshake]: module id: {} include statement {:#?}",
+                module_id.as_str(),
+                stmt,
+              );
+              stmt.include();
+              pending_marks.extend(resolver.reads_of(symbol_box, stmt));
+            }
+          });
+        }
+      }
+    }
+  }
 
-      read_marks.into_iter().for_each(|mark| {
-        let from_root_mark = self.symbol_box.lock().unwrap().find_root(mark);
-        let matched_decls = self.mark_to_stmt.iter().filter(|pair| {
-          let dest_root_mark = self.symbol_box.lock().unwrap().find_root(*pair.key());
-          from_root_mark == dest_root_mark
-        });
+  // Dead branches are dropped before inclusion, so that they don't keep their reads alive. Literal
+  // conditions, such as `if ('production' !== 'production')` brought by `define`, are folded too.
+  fn fold_constants(&mut self) {
+    let symbol_box = symbol_box_mut(&mut self.symbol_box);
+    let treeshake = self.input_options.treeshake.as_ref().unwrap();
+    let constants = Constants::new(
+      self.module_by_id.values().map(|module| module.as_ref()),
+      symbol_box,
+      treeshake,
+    );
+    self.module_by_id.values_mut().for_each(|module| {
+      let root_marks = module
+        .declared_symbols
+        .values()
+        .chain(module.imported_symbols.values())
+        .copied()
+        .collect::<HashSet<_>>();
+      module
+        .statements
+        .iter_mut()
+        .for_each(|stmt| constants.fold_statement(symbol_box, stmt, &root_marks, treeshake));
+    });
+  }
 
-        matched_decls.into_iter().try_for_each(|pair| {
-          // TODO: recursively add `export *` 's mark
-          let (module_id, idx) = pair.value();
-          let module = self.module_by_id.get_mut(module_id).unwrap();
-          let stmt = &mut module.statements[*idx];
-          if !is_decl_or_stmt(&stmt.node) {
-            return std::ops::ControlFlow::Continue(());
-          }
-          log::debug!(
-            "[treeshake]: module id: {} stmts: {:#?}",
-            module_id.as_str(),
-            stmt,
-          );
-          log::debug!("[treeshake]: include statement {:#?}", stmt.node.clone());
-          stmt.include();
-          std::ops::ControlFlow::Break(())
-        });
+  // Runs before namespace members are rewritten, which turns `ns.foo` into plain reads of `foo`.
+  fn remove_unused_members(&mut self) {
+    let symbol_box = symbol_box_mut(&mut self.symbol_box);
+    let resolver = NamespaceResolver::new(
+      self.module_by_id.values().map(|module| module.as_ref()),
+      symbol_box,
+    );
+    let usage = MemberUsage::new(
+      self
+        .module_by_id
+        .values()
+        .flat_map(|module| module.statements.iter())
+        .filter(|stmt| stmt.included),
+      self
+        .resolved_entries
+        .iter()
+        .flat_map(|resolved_id| self.module_by_id[&resolved_id.id].exports.values().copied()),
+      &resolver,
+      symbol_box,
+    );
+    self
+      .module_by_id
+      .values_mut()
+      .flat_map(|module| module.statements.iter_mut())
+      .filter(|stmt| stmt.included)
+      .for_each(|stmt| usage.remove_unused_members(symbol_box, stmt));
+  }
+
+  fn rewrite_namespace_members(&mut self) {
+    let symbol_box = symbol_box_mut(&mut self.symbol_box);
+    let resolver = NamespaceResolver::new(
+      self.module_by_id.values().map(|module| module.as_ref()),
+      symbol_box,
+    );
+    let mut rewriter = NamespaceMemberRewriter {
+      resolver: &resolver,
+      symbol_box,
+    };
+    self
+      .module_by_id
+      .values_mut()
+      .flat_map(|module| module.statements.iter_mut())
+      .filter(|stmt| stmt.included)
+      .for_each(|stmt| stmt.node.visit_mut_with(&mut rewriter));
+  }
+
+  // Calls are pure only if the callees are annotated with `__NO_SIDE_EFFECTS__`, which might be
+  // declared in other modules.
+  fn resolve_no_side_effect_calls(&mut self) {
+    let symbol_box = symbol_box_mut(&mut self.symbol_box);
+    let no_side_effect_fns = self
+      .module_by_id
+      .values()
+      .flat_map(|module| module.no_side_effect_fns.iter())
+      .map(|mark| symbol_box.find_root(*mark))
+      .collect::<HashSet<_>>();
+    self
+      .module_by_id
+      .values_mut()
+      .flat_map(|module| module.statements.iter_mut())
+      .filter(|stmt| stmt.side_effect.is_none())
+      .for_each(|stmt| {
+        let has_impure_call = stmt
+          .callees
+          .iter()
+          .any(|callee| !no_side_effect_fns.contains(&symbol_box.find_root(*callee)));
+        if has_impure_call {
+          stmt.side_effect = Some(SideEffect::FnCall);
+        }
       });
-    }
   }
 
+  // Links `export * from './foo'` per the spec. Local exports shadow star exports, and `default` is
+  // never re-exported. A name provided by several modules as different bindings is ambiguous, so it's
+  // excluded, and it stays ambiguous for modules re-exporting it further.
   pub fn link_module_exports(&mut self) {
-    self.ordered_modules.iter().for_each(|idx| {
+    let symbol_box = symbol_box_mut(&mut self.symbol_box);
+    let mut ambiguous_exports: HashMap<SmolStr, HashSet<JsWord>> = HashMap::new();
+    let mut warnings = vec![];
+    // Dependencies come first, so star exports of barrels are linked before their importers.
+    for idx in self.ordered_modules.iter() {
       let module_id = &self.module_graph[*idx];
-      let module = self.module_by_id.get(module_id).unwrap();
-      // self.module_by_id.get_mut
-      let dep_ids = module
-        .re_export_all_sources
-        .iter()
-        .map(|dep_src| module.resolved_ids.get(dep_src).unwrap().clone().id)
-        .collect::<Vec<_>>();
-      let dep_exports = dep_ids
-        .into_par_iter()
-        .map(|id| self.module_by_id.get(&id).unwrap())
-        .map(|dep_module| (dep_module.id.clone(), dep_module.exports.clone()))
+      // External modules have nothing to link.
+      let module = match self.module_by_id.get(module_id) {
+        Some(module) => module,
+        None => continue,
+      };
+      let mut edges = self
+        .module_graph
+        .edges_directed(*idx, EdgeDirection::Outgoing)
+        .filter(|edge| matches!(edge.weight(), Rel::ReExportAll(_)))
         .collect::<Vec<_>>();
+      edges.sort_by_key(|edge| edge.weight().get_order());
 
-      let module = self.module_by_id.get_mut(module_id).unwrap();
-      dep_exports.into_iter().for_each(|(dep_id, dep_exports)| {
-        dep_exports.into_iter().for_each(|(exported_name, mark)| {
-          assert!(
-            !module.exports.contains_key(&exported_name),
-            "duplicate when export {:?} from {:?} in {:?}",
-            exported_name,
-            dep_id,
-            module.id
+      // name -> (module providing it, node of the module, mark)
+      let mut star_exports: HashMap<JsWord, Vec<(SmolStr, NodeIndex, Mark)>> = HashMap::new();
+      let mut ambiguous: HashSet<JsWord> = HashSet::new();
+      // `export * from 'external'` is kept as it is in the output.
+      edges
+        .iter()
+        .filter_map(|edge| {
+          let dep_module = self.module_by_id.get(&self.module_graph[edge.target()])?;
+          Some((edge.target(), dep_module))
+        })
+        .for_each(|(dep_idx, dep_module)| {
+          let is_shadowed = |name: &JsWord| name == "default" || module.exports.contains_key(name);
+          if let Some(names) = ambiguous_exports.get(&dep_module.id) {
+            ambiguous.extend(names.iter().filter(|name| !is_shadowed(name)).cloned());
+          }
+          dep_module
+            .exports
+            .iter()
+            .filter(|(name, _)| !is_shadowed(name))
+            .for_each(|(name, mark)| {
+              star_exports.entry(name.clone()).or_default().push((
+                dep_module.id.clone(),
+                dep_idx,
+                *mark,
+              ))
+            });
+        });
+
+      let mut star_exports = star_exports.into_iter().collect::<Vec<_>>();
+      star_exports.sort_by(|(a, _), (b, _)| a.cmp(b));
+      let mut linked = vec![];
+      star_exports.into_iter().for_each(|(name, sources)| {
+        if ambiguous.contains(&name) {
+          return;
+        }
+        // Imports and re-exports aren't linked yet, so the same binding might be provided through
+        // different marks.
+        let mut originals = sources.iter().map(|(_, dep_idx, mark)| {
+          let original = find_original_mark(
+            &self.module_graph,
+            &self.module_by_id,
+            symbol_box,
+            *dep_idx,
+            *mark,
           );
-          module.exports.insert(exported_name, mark);
+          symbol_box.find_root(original)
         });
+        let root = originals.next().unwrap();
+        if originals.all(|original| original == root) {
+          linked.push((name, sources[0].2));
+        } else {
+          let mut sources = sources
+            .iter()
+            .map(|(id, ..)| format!("\"{}\"", relative_id(id.to_string())))
+            .collect::<Vec<_>>();
+          let last = sources.pop().unwrap();
+          warnings.push(
+            Warning::new(
+              WarningCode::NamespaceConflict,
+              format!(
+                "Conflicting namespaces: \"{}\" re-exports \"{}\" from one of the modules {} and {} (will be ignored)",
+                relative_id(module_id.to_string()),
+                name,
+                sources.join(", "),
+                last,
+              ),
+            )
+            .with_id(module_id.as_str()),
+          );
+          ambiguous.insert(name);
+        }
       });
-    });
+
+      let module = self.module_by_id.get_mut(module_id).unwrap();
+      module.exports.extend(linked);
+      ambiguous_exports.insert(module_id.clone(), ambiguous);
+    }
+    warnings.into_iter().for_each(|warning| self.warn(warning));
   }
 
-  pub fn link_module(&mut self) {
-    self.ordered_modules.iter().for_each(|idx| {
-      let edges = self
+  pub fn link_module(&mut self) -> Result<(), BuildError> {
+    let symbol_box = symbol_box_mut(&mut self.symbol_box);
+    self.ordered_modules.iter().try_for_each(|idx| {
+      // Edges are added in the order modules are scanned. Link them in the source order instead, so
+      // that the first reported error is stable.
+      let mut edges = self
         .module_graph
-        .edges_directed(*idx, EdgeDirection::Outgoing);
-      edges.for_each(|edge| {
+        .edges_directed(*idx, EdgeDirection::Outgoing)
+        .collect::<Vec<_>>();
+      edges.sort_by_key(|edge| edge.weight().get_order());
+      edges.into_iter().try_for_each(|edge| {
         log::debug!(
           "[graph]: link module from {:?} to {:?}",
           &self.module_graph[*idx],
@@ -325,8 +793,18 @@ impl Graph {
           Rel::ReExport(info) => Some(info),
           _ => None,
         };
+        // Bindings imported from external modules are left as they are.
+        if !self
+          .module_by_id
+          .contains_key(&self.module_graph[edge.target()])
+        {
+          return Ok(());
+        }
         if let Some(rel_info) = rel_info {
-          rel_info.names.iter().for_each(|specifier| {
+          // The last suggested name wins, so specifiers are visited in a stable order.
+          let mut specifiers = rel_info.names.iter().collect::<Vec<_>>();
+          specifiers.sort_by(|a, b| (&a.original, &a.used).cmp(&(&b.original, &b.used)));
+          specifiers.into_iter().try_for_each(|specifier| {
             let dep_module = self
               .module_by_id
               .get_mut(&self.module_graph[edge.target()])
@@ -350,19 +828,88 @@ impl Graph {
               dep_module.include_namespace(self.mark_to_stmt.clone());
             }
 
-            let dep_module_exported_mark = dep_module
-              .exports
-              .get(&specifier.original)
-              .expect("Not found");
-
-            self
-              .symbol_box
-              .lock()
-              .unwrap()
-              .union(specifier.mark, *dep_module_exported_mark);
-          });
+            let dep_module_exported_mark =
+              dep_module.exports.get(&specifier.original).ok_or_else(|| {
+                let importer = &self.module_graph[*idx];
+                BuildError::new(
+                  ErrorCode::MissingExport,
+                  format!(
+                    "\"{}\" is not exported by \"{}\", imported by \"{}\"",
+                    specifier.original,
+                    relative_id(dep_module.id.to_string()),
+                    relative_id(importer.to_string()),
+                  ),
+                )
+                .with_id(importer.as_str())
+                .with_span(specifier.span)
+              })?;
+
+            symbol_box.union(specifier.mark, *dep_module_exported_mark);
+            Ok(())
+          })?;
         }
+        Ok(())
+      })
+    })
+  }
+}
+
+// Follows imports, re-exports and star exports of the module at `idx` back to the module that
+// declares the binding of `mark`.
+fn find_original_mark(
+  module_graph: &ModulePetGraph,
+  module_by_id: &HashMap<SmolStr, Box<Module>>,
+  symbol_box: &mut SymbolBox,
+  mut idx: NodeIndex,
+  mut mark: Mark,
+) -> Mark {
+  let mut visited = HashSet::new();
+  while visited.insert((idx, symbol_box.find_root(mark))) {
+    let next = module_graph
+      .edges_directed(idx, EdgeDirection::Outgoing)
+      .find_map(|edge| {
+        // Bindings of external modules are the original ones.
+        let dep_module = module_by_id.get(&module_graph[edge.target()])?;
+        let dep_mark = match edge.weight() {
+          Rel::Import(info) | Rel::ReExport(info) => info
+            .names
+            .iter()
+            .filter(|specifier| &specifier.original != "*")
+            .find(|specifier| symbol_box.find_root(specifier.mark) == symbol_box.find_root(mark))
+            .and_then(|specifier| dep_module.exports.get(&specifier.original))
+            .copied(),
+          // Star exports are linked with the marks of the modules providing them.
+          Rel::ReExportAll(_) => dep_module
+            .exports
+            .iter()
+            .any(|(name, dep_mark)| name != "*" && *dep_mark == mark)
+            .then(|| mark),
+        }?;
+        Some((edge.target(), dep_mark))
       });
-    });
+    match next {
+      Some((next_idx, next_mark)) => {
+        idx = next_idx;
+        mark = next_mark;
+      }
+      None => break,
+    }
   }
+  mark
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+  payload
+    .downcast_ref::<&str>()
+    .map(|message| message.to_string())
+    .or_else(|| payload.downcast_ref::<String>().cloned())
+    .unwrap_or_default()
+}
+
+// Workers never touch the symbol box, so it's exclusively owned by the graph until chunks are
+// generated. Linking doesn't need to lock it.
+fn symbol_box_mut(symbol_box: &mut Arc<Mutex<SymbolBox>>) -> &mut SymbolBox {
+  Arc::get_mut(symbol_box)
+    .and_then(|symbol_box| symbol_box.get_mut().ok())
+    .expect("the symbol box is shared while building the graph")
 }
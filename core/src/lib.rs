@@ -2,15 +2,21 @@
 
 pub mod bundle;
 pub mod chunk;
+pub mod constants;
+pub mod error;
 pub mod external_module;
 pub mod graph;
 // pub mod linker;
+pub mod members;
 pub mod module;
+pub mod namespace;
+pub mod plugins;
 pub mod scanner;
 // pub mod statement;
 pub mod renamer;
 pub mod types;
 pub mod utils;
+pub mod warning;
 pub mod worker;
 
 use graph::Graph;
@@ -18,7 +24,10 @@ use structs::RolldownOutput;
 pub use swc_ecma_ast as ast;
 use types::{NormalizedInputOptions, NormalizedOutputOptions};
 
-use crate::bundle::Bundle;
+use crate::{
+  bundle::Bundle,
+  error::{BuildError, ErrorCode, RolldownError},
+};
 
 // refactor
 pub mod compiler;
@@ -34,17 +43,23 @@ pub struct RolldownBuild {
 }
 
 impl RolldownBuild {
-  pub fn new(options: NormalizedInputOptions) -> Self {
+  pub fn new(options: NormalizedInputOptions) -> Result<Self, RolldownError> {
     let mut graph = Graph::new(options);
-    graph.build();
-    Self { graph }
+    graph.build()?;
+    Ok(Self { graph })
   }
 
-  pub fn generate(self, options: NormalizedOutputOptions) -> Vec<RolldownOutput> {
+  pub fn generate(
+    self,
+    options: NormalizedOutputOptions,
+  ) -> Result<Vec<RolldownOutput>, RolldownError> {
     handle_generate_write(false, self.graph, options)
   }
 
-  pub fn write(self, options: NormalizedOutputOptions) -> Vec<RolldownOutput> {
+  pub fn write(
+    self,
+    options: NormalizedOutputOptions,
+  ) -> Result<Vec<RolldownOutput>, RolldownError> {
     handle_generate_write(true, self.graph, options)
   }
 }
@@ -54,9 +69,15 @@ fn handle_generate_write(
   is_write: bool,
   graph: Graph,
   output_options: NormalizedOutputOptions,
-) -> Vec<RolldownOutput> {
-  if is_write {
-    assert!(output_options.dir.is_some() || output_options.file.is_some());
+) -> Result<Vec<RolldownOutput>, RolldownError> {
+  if is_write && output_options.dir.is_none() && output_options.file.is_none() {
+    return Err(
+      BuildError::new(
+        ErrorCode::MissingOption,
+        "You must specify \"output.file\" or \"output.dir\" for the build",
+      )
+      .into(),
+    );
   }
   let mut bundle = Bundle::new(graph, output_options);
   let output = bundle.generate();
@@ -65,23 +86,27 @@ fn handle_generate_write(
     .map(|(_, output_chunk)| RolldownOutput::Chunk(output_chunk))
     .collect::<Vec<_>>();
 
-  output.iter().for_each(|output| {
-    write_output_file(output, &bundle.output_options);
-  });
+  if is_write {
+    output
+      .iter()
+      .try_for_each(|output| write_output_file(output, &bundle.output_options))?;
+  }
 
-  output
+  Ok(output)
 }
 
-fn write_output_file(output_file: &RolldownOutput, output_options: &NormalizedOutputOptions) {
-  let file_name = nodejs_path::resolve!(
-    &output_options
-      .dir
-      .clone()
-      .unwrap_or_else(|| nodejs_path::dirname(output_options.file.as_ref().unwrap())),
-    output_file.get_file_name()
-  );
+fn write_output_file(
+  output_file: &RolldownOutput,
+  output_options: &NormalizedOutputOptions,
+) -> Result<(), RolldownError> {
+  let dir = match (&output_options.dir, &output_options.file) {
+    (Some(dir), _) => dir.clone(),
+    (None, Some(file)) => nodejs_path::dirname(file),
+    (None, None) => unreachable!("`dir` or `file` is checked before writing"),
+  };
+  let file_name = nodejs_path::resolve!(&dir, output_file.get_file_name());
 
-  std::fs::create_dir_all(nodejs_path::dirname(&file_name)).unwrap();
+  std::fs::create_dir_all(nodejs_path::dirname(&file_name)).map_err(RolldownError::IO)?;
   log::info!("file_name {}", file_name);
-  std::fs::write(file_name, output_file.get_content()).unwrap();
+  std::fs::write(file_name, output_file.get_content()).map_err(RolldownError::IO)
 }
@@ -0,0 +1,222 @@
+use std::collections::{HashMap, HashSet};
+
+use swc_atoms::JsWord;
+use swc_common::{Mark, SyntaxContext};
+use swc_ecma_ast::{
+  BindingIdent, Class, ClassDecl, ClassExpr, ClassMember, Decl, ExportDecl, Expr, Ident,
+  ModuleDecl, ModuleItem, ObjectLit, Pat, Prop, PropName, PropOrSpread, Stmt, ThisExpr, VarDecl,
+  VarDeclKind,
+};
+use swc_ecma_visit::{noop_visit_type, Visit, VisitWith};
+
+use crate::{
+  ext::SyntaxContextExt, namespace::NamespaceResolver, statement::Statement, symbol_box::SymbolBox,
+};
+
+// Tracks which members of top-level classes and const object literals are used. A binding escapes
+// once it's used other than `foo.bar`, such as `new Foo()` or `console.log(foo)`, and then all of
+// its members are kept.
+pub struct MemberUsage {
+  escaped: HashSet<Mark>,
+  // root mark -> properties accessed by `foo.bar`
+  used: HashMap<Mark, HashSet<JsWord>>,
+}
+
+impl MemberUsage {
+  pub fn new<'a>(
+    statements: impl Iterator<Item = &'a Statement>,
+    entry_exports: impl Iterator<Item = Mark>,
+    resolver: &NamespaceResolver,
+    symbol_box: &mut SymbolBox,
+  ) -> Self {
+    let mut escaped: HashSet<Mark> = entry_exports
+      .map(|mark| symbol_box.find_root(mark))
+      .collect();
+    let mut used: HashMap<Mark, HashSet<JsWord>> = HashMap::new();
+    statements.for_each(|stmt| {
+      stmt
+        .reads
+        .iter()
+        .chain(stmt.writes.iter())
+        .for_each(|mark| {
+          escaped.insert(symbol_box.find_root(*mark));
+        });
+      stmt.member_reads.iter().for_each(|(obj, prop)| {
+        // `ns.foo` reads the export `foo` itself.
+        match resolver.resolve(symbol_box, *obj, prop) {
+          Some(exported) => {
+            escaped.insert(symbol_box.find_root(exported));
+          }
+          None => {
+            used
+              .entry(symbol_box.find_root(*obj))
+              .or_default()
+              .insert(prop.clone());
+          }
+        }
+      });
+    });
+    Self { escaped, used }
+  }
+
+  fn used_props(&self, symbol_box: &mut SymbolBox, ident: &Ident) -> Option<HashSet<JsWord>> {
+    if ident.span.ctxt == SyntaxContext::empty() {
+      return None;
+    }
+    let root = symbol_box.find_root(ident.span.ctxt.as_mark());
+    if self.escaped.contains(&root) {
+      return None;
+    }
+    Some(self.used.get(&root).cloned().unwrap_or_default())
+  }
+
+  // Members are only dropped if the declaration itself is pure, or if dropping them can't remove
+  // side effects.
+  pub fn remove_unused_members(&self, symbol_box: &mut SymbolBox, stmt: &mut Statement) {
+    let is_pure = stmt.side_effect.is_none();
+    let decl = match &mut stmt.node {
+      ModuleItem::Stmt(Stmt::Decl(decl)) => decl,
+      ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => decl,
+      _ => return,
+    };
+    match decl {
+      Decl::Class(ClassDecl { ident, class, .. }) => {
+        if let Some(used) = self.used_props(symbol_box, ident) {
+          remove_unused_class_members(class, &used, is_pure);
+        }
+      }
+      Decl::Var(VarDecl {
+        kind: VarDeclKind::Const,
+        decls,
+        ..
+      }) => decls.iter_mut().for_each(|declarator| {
+        if let (Pat::Ident(BindingIdent { id, .. }), Some(init)) =
+          (&declarator.name, declarator.init.as_deref_mut())
+        {
+          if let Some(used) = self.used_props(symbol_box, id) {
+            match init {
+              Expr::Object(object) => remove_unused_props(object, &used, is_pure),
+              Expr::Class(ClassExpr { class, .. }) => {
+                remove_unused_class_members(class, &used, is_pure)
+              }
+              _ => {}
+            }
+          }
+        }
+      }),
+      _ => {}
+    }
+  }
+}
+
+#[derive(Default)]
+struct ThisFinder {
+  found: bool,
+}
+
+impl Visit for ThisFinder {
+  noop_visit_type!();
+
+  fn visit_this_expr(&mut self, _: &ThisExpr) {
+    self.found = true;
+  }
+}
+
+fn uses_this<N: VisitWith<ThisFinder>>(node: &N) -> bool {
+  let mut finder = ThisFinder::default();
+  node.visit_with(&mut finder);
+  finder.found
+}
+
+fn static_prop_name(key: &PropName) -> Option<JsWord> {
+  match key {
+    PropName::Ident(ident) => Some(ident.sym.clone()),
+    PropName::Str(str) => Some(str.value.clone()),
+    _ => None,
+  }
+}
+
+fn is_pure_value(expr: &Expr) -> bool {
+  match expr {
+    Expr::Lit(_) | Expr::Fn(_) | Expr::Arrow(_) => true,
+    Expr::Ident(ident) => ident.span.ctxt != SyntaxContext::empty(),
+    Expr::Tpl(tpl) => tpl.exprs.is_empty(),
+    _ => false,
+  }
+}
+
+fn is_static_member(member: &ClassMember) -> bool {
+  match member {
+    ClassMember::Method(method) => method.is_static,
+    ClassMember::PrivateMethod(method) => method.is_static,
+    ClassMember::ClassProp(prop) => prop.is_static,
+    ClassMember::PrivateProp(prop) => prop.is_static,
+    ClassMember::StaticBlock(_) => true,
+    _ => false,
+  }
+}
+
+// The class is never instantiated, so only static members accessed by `Foo.bar` are reachable.
+fn remove_unused_class_members(class: &mut Class, used: &HashSet<JsWord>, is_pure: bool) {
+  // `this` of static members is the class itself, which might be used dynamically.
+  let has_dynamic_access = !class.decorators.is_empty()
+    || used.contains(&JsWord::from("prototype"))
+    || class
+      .body
+      .iter()
+      .any(|member| is_static_member(member) && uses_this(member));
+  if has_dynamic_access {
+    return;
+  }
+  class.body.retain(|member| match member {
+    ClassMember::Method(method) => match static_prop_name(&method.key) {
+      Some(name) => method.is_static && used.contains(&name),
+      None => true,
+    },
+    ClassMember::ClassProp(prop) if prop.is_static => {
+      let is_removable = is_pure || prop.value.as_deref().map_or(true, is_pure_value);
+      match static_prop_name(&prop.key) {
+        Some(name) => used.contains(&name) || !is_removable,
+        None => true,
+      }
+    }
+    _ => true,
+  });
+}
+
+fn prop_name(prop: &Prop) -> Option<JsWord> {
+  match prop {
+    Prop::Shorthand(ident) => Some(ident.sym.clone()),
+    Prop::KeyValue(prop) => static_prop_name(&prop.key),
+    Prop::Getter(prop) => static_prop_name(&prop.key),
+    Prop::Setter(prop) => static_prop_name(&prop.key),
+    Prop::Method(prop) => static_prop_name(&prop.key),
+    Prop::Assign(_) => None,
+  }
+}
+
+fn remove_unused_props(object: &mut ObjectLit, used: &HashSet<JsWord>, is_pure: bool) {
+  // Methods might access other properties with `this`, and spreads or computed keys could override
+  // any property.
+  let has_dynamic_access = uses_this(object)
+    || !object.props.iter().all(|prop| match prop {
+      PropOrSpread::Prop(prop) => prop_name(&**prop).is_some(),
+      PropOrSpread::Spread(_) => false,
+    });
+  if has_dynamic_access {
+    return;
+  }
+  object.props.retain(|prop| match prop {
+    PropOrSpread::Prop(prop) => {
+      let is_removable = match &**prop {
+        Prop::KeyValue(prop) => is_pure || is_pure_value(&prop.value),
+        _ => true,
+      };
+      match prop_name(&**prop) {
+        Some(name) => used.contains(&name) || &*name == "__proto__" || !is_removable,
+        None => true,
+      }
+    }
+    PropOrSpread::Spread(_) => true,
+  });
+}
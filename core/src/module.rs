@@ -1,7 +1,10 @@
 use crate::ast;
+use crate::error::{BuildError, ErrorCode};
+use crate::ext::{MarkExt, SyntaxContextExt};
+use crate::plugin_driver::PluginDriver;
 use crate::scanner::ModuleItemInfo;
 use crate::statement::Statement;
-use crate::symbol_box::SymbolBox;
+use crate::symbol_box::{renumber_mark, SymbolBox};
 
 use crate::utils::{ast_sugar, resolve_id};
 use dashmap::DashMap;
@@ -25,10 +28,10 @@ use swc_ecma_ast::Ident;
 use crate::utils::is_decl_or_stmt;
 use swc_ecma_codegen::text_writer::WriteJs;
 use swc_ecma_codegen::Emitter;
-use swc_ecma_visit::{noop_visit_mut_type, VisitMut};
+use swc_ecma_visit::{noop_visit_mut_type, VisitMut, VisitMutWith};
 
 use crate::scanner::rel::{ExportDesc, ReExportDesc};
-use crate::types::ResolvedId;
+use crate::types::{NormalizedInputOptions, ResolvedId};
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Namespace {
@@ -53,6 +56,17 @@ pub struct Module {
   pub namespace: Namespace,
   pub is_user_defined_entry_point: bool,
   pub module_span: Span,
+  // Top-level functions annotated with `/*#__NO_SIDE_EFFECTS__*/`.
+  pub no_side_effect_fns: HashSet<Mark>,
+  // Globals accessed by the module, which declarations of other modules must not shadow.
+  pub global_reads: HashSet<JsWord>,
+  // Whether the module is kept for its side effects even if none of its exports are used.
+  pub module_side_effects: bool,
+  // Number of marks allocated by the scanner of the module.
+  pub mark_count: u32,
+  // Marks declared by statements, and the indexes of the statements. They're registered to
+  // `Graph::mark_to_stmt` once marks are renumbered.
+  pub stmt_marks: Vec<(Mark, usize)>,
   // pub module_item_infos: Vec<ModuleItemInfo>,
 }
 
@@ -73,6 +87,11 @@ impl Module {
       namespace: Default::default(),
       is_user_defined_entry_point: false,
       module_span: Take::dummy(),
+      no_side_effect_fns: Default::default(),
+      global_reads: Default::default(),
+      module_side_effects: true,
+      mark_count: 0,
+      stmt_marks: Default::default(),
     }
   }
 
@@ -86,11 +105,11 @@ impl Module {
     // We couldn't deal with `export * from './foo'` now.
   }
 
-  pub fn bind_local_references(&self, symbol_box: &mut SymbolBox) {
+  pub fn bind_local_references(&self, symbol_box: &mut SymbolBox) -> Result<(), BuildError> {
     self
       .local_exports
       .iter()
-      .for_each(|(_exported_name, export_desc)| {
+      .try_for_each(|(_exported_name, export_desc)| {
         let refernenced_name = export_desc
           .identifier
           .as_ref()
@@ -98,19 +117,17 @@ impl Module {
         if refernenced_name == "default" {
           // This means that the module's `export default` is a value. Sush as `export default 1`
           // No name to bind. And we need to generate a name for it lately.
-          return;
+          return Ok(());
         }
-        let symbol_mark = self.resolve_mark(refernenced_name);
+        let symbol_mark = self
+          .resolve_mark(refernenced_name)
+          .map_err(|err| err.with_span(export_desc.span))?;
         symbol_box.union(export_desc.mark, symbol_mark);
-      });
+        Ok(())
+      })
   }
 
-  pub fn set_statements(
-    &mut self,
-    ast: ast::Module,
-    module_item_infos: Vec<ModuleItemInfo>,
-    mark_to_stmt: Arc<DashMap<Mark, (SmolStr, usize)>>,
-  ) {
+  pub fn set_statements(&mut self, ast: ast::Module, module_item_infos: Vec<ModuleItemInfo>) {
     self.module_span = ast.span;
     self.statements = ast
       .body
@@ -121,51 +138,83 @@ impl Module {
         let is_decl_or_stmt = is_decl_or_stmt(&node);
         let mut stmt = Statement::new(node);
         if let Some(export_mark) = info.export_mark {
-          mark_to_stmt
-            .entry(export_mark)
-            .or_insert_with(|| (self.id.clone(), idx));
+          self.stmt_marks.push((export_mark, idx));
         }
         info.declared.iter().for_each(|(name, mark)| {
           self.definitions.insert(name.clone(), idx);
 
           // Skip declarations brought by `import`
           if is_decl_or_stmt {
-            mark_to_stmt
-              .entry(*mark)
-              .or_insert_with(|| (self.id.clone(), idx));
+            self.stmt_marks.push((*mark, idx));
           }
         });
         stmt.writes = info.writes;
         stmt.reads = info.reads;
         stmt.side_effect = info.side_effect;
-        // TODO: add it back later
-        // if stmt.side_effect.is_none() {
-        //   let has_unknown_name = stmt
-        //     .reads
-        //     .iter()
-        //     .chain(stmt.writes.iter())
-        //     .any(|name| !self.declared_symbols.contains_key(name));
-        //   if has_unknown_name {
-        //     // TODO: Should do this in Scanner
-        //     stmt.side_effect = Some(SideEffect::VisitGlobalVar)
-        //   }
-        // }
+        stmt.callees = info.callees;
+        stmt.member_reads = info.member_reads;
 
         stmt
       })
       .collect();
   }
 
-  pub fn include_mark(&mut self, name: &JsWord, mark: &Mark) {
-    log::debug!(
-      "[treeshake]: definition `{}` included in `{}`",
-      &name,
-      self.id
-    );
-    if let Some(&stmt_idx) = self.definitions.get(name) {
-      let stmt = &mut self.statements[stmt_idx];
-      stmt.reads.insert(*mark);
-    }
+  // Marks are numbered from `1` in each module while scanning. Moves them after `offset`, which is
+  // the number of marks of modules executed earlier, so that they don't depend on which worker scans
+  // a module first.
+  pub fn renumber_marks(&mut self, offset: u32) {
+    let mut renumberer = MarkRenumberer { offset };
+    self.statements.iter_mut().for_each(|stmt| {
+      stmt.node.visit_mut_with(&mut renumberer);
+      stmt
+        .declared
+        .values_mut()
+        .for_each(|mark| *mark = renumber_mark(*mark, offset));
+      stmt.reads = stmt
+        .reads
+        .iter()
+        .map(|mark| renumber_mark(*mark, offset))
+        .collect();
+      stmt.writes = stmt
+        .writes
+        .iter()
+        .map(|mark| renumber_mark(*mark, offset))
+        .collect();
+      stmt.callees = stmt
+        .callees
+        .iter()
+        .map(|mark| renumber_mark(*mark, offset))
+        .collect();
+      stmt.member_reads = stmt
+        .member_reads
+        .iter()
+        .map(|(mark, prop)| (renumber_mark(*mark, offset), prop.clone()))
+        .collect();
+    });
+    self
+      .local_exports
+      .values_mut()
+      .for_each(|desc| desc.mark = renumber_mark(desc.mark, offset));
+    self
+      .re_exports
+      .values_mut()
+      .for_each(|desc| desc.mark = renumber_mark(desc.mark, offset));
+    self
+      .exports
+      .values_mut()
+      .chain(self.declared_symbols.values_mut())
+      .chain(self.imported_symbols.values_mut())
+      .for_each(|mark| *mark = renumber_mark(*mark, offset));
+    self.no_side_effect_fns = self
+      .no_side_effect_fns
+      .iter()
+      .map(|mark| renumber_mark(*mark, offset))
+      .collect();
+    self.namespace.mark = renumber_mark(self.namespace.mark, offset);
+    self
+      .stmt_marks
+      .iter_mut()
+      .for_each(|(mark, _)| *mark = renumber_mark(*mark, offset));
   }
 
   pub fn include(&mut self, only_side_effects: bool) {
@@ -188,22 +237,47 @@ impl Module {
     self.suggested_names.insert(name, suggested);
   }
 
-  pub fn resolve_id(&self, dep_src: &JsWord) -> ResolvedId {
+  // `name`, or `name$1`, `name$2`, ... if the module declares `name` itself.
+  fn undeclared_name(&self, name: JsWord) -> JsWord {
+    if !self.declared_symbols.contains_key(&name) {
+      return name;
+    }
+    (1..)
+      .map(|i| JsWord::from(format!("{}${}", name, i)))
+      .find(|candidate| !self.declared_symbols.contains_key(candidate))
+      .unwrap_or(name)
+  }
+
+  pub fn resolve_id(
+    &self,
+    dep_src: &JsWord,
+    input_options: &NormalizedInputOptions,
+    plugin_driver: &PluginDriver,
+  ) -> Result<ResolvedId, BuildError> {
+    if let Some(resolved_id) = self.resolved_ids.get(dep_src) {
+      return Ok(resolved_id.clone());
+    }
+    let resolved_id = resolve_id(dep_src, Some(&self.id), input_options, plugin_driver)?;
     self
       .resolved_ids
-      .entry(dep_src.clone())
-      .or_insert_with(|| resolve_id(dep_src, Some(&self.id), false))
-      .clone()
+      .insert(dep_src.clone(), resolved_id.clone());
+    Ok(resolved_id)
   }
 
-  pub fn resolve_mark(&self, name: &JsWord) -> Mark {
-    *self.declared_symbols.get(name).unwrap_or_else(|| {
-      self
-        .imported_symbols
-        .get(name)
-        // TODO: how can we support global exports? such as `export { Math }`
-        .unwrap_or_else(|| panic!("unkown name: {:?} for module {}", name, self.id))
-    })
+  pub fn resolve_mark(&self, name: &JsWord) -> Result<Mark, BuildError> {
+    self
+      .declared_symbols
+      .get(name)
+      .or_else(|| self.imported_symbols.get(name))
+      .copied()
+      // TODO: how can we support global exports? such as `export { Math }`
+      .ok_or_else(|| {
+        BuildError::new(
+          ErrorCode::UndefinedExport,
+          format!("Exported variable \"{}\" is not defined", name),
+        )
+        .with_id(self.id.as_str())
+      })
   }
 
   pub fn trim_exports(&mut self) {
@@ -218,6 +292,29 @@ impl Module {
       .collect();
   }
 
+  // Rewrite the sources of kept external imports and exports, such as `import 'react'`.
+  pub fn set_external_import_paths(&mut self, get_import_path: impl Fn(&str) -> Option<String>) {
+    let resolved_ids = &self.resolved_ids;
+    self.statements.iter_mut().for_each(|stmt| {
+      let src = match &mut stmt.node {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(import_decl)) => Some(&mut import_decl.src),
+        ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export_all)) => Some(&mut export_all.src),
+        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(named_export)) => named_export.src.as_mut(),
+        _ => None,
+      };
+      if let Some(src) = src {
+        let import_path = resolved_ids
+          .get(&src.value)
+          .filter(|resolved_id| resolved_id.external)
+          .and_then(|resolved_id| get_import_path(&resolved_id.id));
+        if let Some(import_path) = import_path {
+          src.value = import_path.into();
+          src.span = DUMMY_SP;
+        }
+      }
+    });
+  }
+
   pub fn generate_exports(&mut self) {
     if !self.exports.is_empty() {
       let export_decl = ast_sugar::export(&self.exports);
@@ -229,24 +326,22 @@ impl Module {
 
   pub fn include_namespace(&mut self, mark_to_stmt: Arc<DashMap<Mark, (SmolStr, usize)>>) {
     if !self.namespace.included {
-      let suggested_default_export_name = self
-        .suggested_names
-        .get(&"*".into())
-        .cloned()
-        .unwrap_or_else(|| {
-          (get_valid_name(nodejs_path::parse(&self.id).name) + "namespace").into()
-        });
-      // TODO: We should generate a name which has no conflict.
-      // TODO: We might need to check if the name already exsits.
-      assert!(!self
-        .declared_symbols
-        .contains_key(&suggested_default_export_name));
+      let suggested_default_export_name = self.undeclared_name(
+        self
+          .suggested_names
+          .get(&"*".into())
+          .cloned()
+          .unwrap_or_else(|| {
+            (get_valid_name(nodejs_path::parse(&self.id).name) + "namespace").into()
+          }),
+      );
       self.local_exports.insert(
         "*".into(),
         ExportDesc {
           identifier: None,
           mark: self.namespace.mark,
           local_name: suggested_default_export_name.clone(),
+          span: DUMMY_SP,
         },
       );
       self.exports.insert("*".into(), self.namespace.mark);
@@ -255,6 +350,8 @@ impl Module {
         &self.exports,
       );
       let mut s = Statement::new(ast::ModuleItem::Stmt(namespace));
+      // The namespace object references every export of the module.
+      s.reads = self.exports.values().copied().collect();
       let idx = self.statements.len();
       self
         .definitions
@@ -312,6 +409,20 @@ impl Hash for Module {
   }
 }
 
+struct MarkRenumberer {
+  offset: u32,
+}
+
+impl VisitMut for MarkRenumberer {
+  noop_visit_mut_type!();
+
+  fn visit_mut_ident(&mut self, ident: &mut Ident) {
+    if ident.span.ctxt != SyntaxContext::empty() {
+      ident.span.ctxt = renumber_mark(ident.span.ctxt.as_mark(), self.offset).as_ctxt();
+    }
+  }
+}
+
 #[derive(Clone, Copy)]
 struct ClearMark;
 impl VisitMut for ClearMark {
@@ -333,15 +444,13 @@ pub fn fold_export_decl_to_decl(
   // is_entry: bool,
 ) -> ModuleItem {
   let mut get_default_ident = || {
-    let suggested_default_export_name = module
-      .suggested_names
-      .get(&"default".into())
-      .cloned()
-      .unwrap_or_else(|| get_valid_name(nodejs_path::parse(&module.id).name).into());
-
-    assert!(!module
-      .declared_symbols
-      .contains_key(&suggested_default_export_name));
+    let suggested_default_export_name = module.undeclared_name(
+      module
+        .suggested_names
+        .get(&"default".into())
+        .cloned()
+        .unwrap_or_else(|| get_valid_name(nodejs_path::parse(&module.id).name).into()),
+    );
     module.declared_symbols.insert(
       suggested_default_export_name.clone(),
       *module.exports.get(&"default".into()).unwrap(),
@@ -368,6 +477,19 @@ pub fn fold_export_decl_to_decl(
         }))),
         _ => ModuleItem::dummy(),
       },
+      // keep imports of external modules and remove the internal ones.
+      // import React from 'react'
+      ModuleDecl::Import(import_decl) => {
+        if module
+          .resolved_ids
+          .get(&import_decl.src.value)
+          .map_or(false, |resolved_id| resolved_id.external)
+        {
+          ModuleItem::ModuleDecl(ModuleDecl::Import(import_decl))
+        } else {
+          ModuleItem::dummy()
+        }
+      }
       ModuleDecl::ExportAll(export_all) => {
         // keep external module as it (we may use it later on code-gen) and internal modules removed.
         // export * from 'react'
@@ -0,0 +1,103 @@
+use std::collections::{HashMap, HashSet};
+
+use swc_atoms::JsWord;
+use swc_common::{Mark, SyntaxContext};
+use swc_ecma_ast::{Expr, Ident};
+use swc_ecma_visit::{noop_visit_mut_type, VisitMut, VisitMutWith};
+
+use crate::{
+  ext::{MarkExt, SyntaxContextExt},
+  module::Module,
+  scanner::helper::static_member_prop,
+  statement::Statement,
+  symbol_box::SymbolBox,
+};
+
+// Resolves `ns.foo` to the export `foo` directly, if `ns` is a namespace of a module. The namespace
+// object is only needed if it's used in other ways, such as `console.log(ns)` or `ns[key]`.
+pub struct NamespaceResolver {
+  // root mark of the namespace -> exports of the module
+  namespaces: HashMap<Mark, HashMap<JsWord, Mark>>,
+  // Root marks declared by modules. Exports of external modules can't be referenced directly.
+  declared_roots: HashSet<Mark>,
+}
+
+impl NamespaceResolver {
+  pub fn new<'a>(
+    modules: impl Iterator<Item = &'a Module> + Clone,
+    symbol_box: &mut SymbolBox,
+  ) -> Self {
+    let namespaces = modules
+      .clone()
+      .filter(|module| module.namespace.included)
+      .map(|module| {
+        (
+          symbol_box.find_root(module.namespace.mark),
+          module.exports.clone(),
+        )
+      })
+      .collect();
+    let declared_roots = modules
+      .flat_map(|module| module.declared_symbols.values())
+      .map(|mark| symbol_box.find_root(*mark))
+      .collect();
+    Self {
+      namespaces,
+      declared_roots,
+    }
+  }
+
+  pub fn resolve(&self, symbol_box: &mut SymbolBox, obj: Mark, prop: &JsWord) -> Option<Mark> {
+    let exports = self.namespaces.get(&symbol_box.find_root(obj))?;
+    let exported = *exports.get(prop).filter(|_| prop != "*")?;
+    self
+      .declared_roots
+      .contains(&symbol_box.find_root(exported))
+      .then(|| exported)
+  }
+
+  // Marks read by the statement. Unresolved `foo.bar` reads `foo` itself.
+  pub fn reads_of(&self, symbol_box: &mut SymbolBox, stmt: &Statement) -> Vec<Mark> {
+    stmt
+      .reads
+      .iter()
+      .copied()
+      .chain(
+        stmt
+          .member_reads
+          .iter()
+          .map(|(obj, prop)| self.resolve(symbol_box, *obj, prop).unwrap_or(*obj))
+          .collect::<Vec<_>>(),
+      )
+      .collect()
+  }
+}
+
+// Rewrites `ns.foo` to `foo`, which is renamed by `Renamer` later.
+pub struct NamespaceMemberRewriter<'a> {
+  pub resolver: &'a NamespaceResolver,
+  pub symbol_box: &'a mut SymbolBox,
+}
+
+impl<'a> VisitMut for NamespaceMemberRewriter<'a> {
+  noop_visit_mut_type!();
+
+  fn visit_mut_expr(&mut self, expr: &mut Expr) {
+    expr.visit_mut_children_with(self);
+    if let Expr::Member(member_expr) = expr {
+      let resolved = match (
+        member_expr.obj.as_ref(),
+        static_member_prop(&member_expr.prop),
+      ) {
+        (Expr::Ident(obj), Some(prop)) if obj.span.ctxt != SyntaxContext::empty() => self
+          .resolver
+          .resolve(self.symbol_box, obj.span.ctxt.as_mark(), &prop)
+          .map(|exported| (prop, member_expr.span.with_ctxt(exported.as_ctxt()))),
+        _ => None,
+      };
+      if let Some((prop, span)) = resolved {
+        *expr = Expr::Ident(Ident::new(prop, span));
+      }
+    }
+  }
+}
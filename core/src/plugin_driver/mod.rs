@@ -1,10 +1,10 @@
 use std::sync::Mutex;
 
-use crate::types::ResolveIdResult;
+use crate::types::{LoadResult, ResolveIdResult};
 
 #[derive(Default)]
 pub struct PluginDriver {
-  pub plugins: Mutex<Vec<Box<dyn Plugin + Send>>>,
+  pub plugins: Mutex<Vec<Box<dyn Plugin + Send + Sync>>>,
 }
 
 impl PluginDriver {
@@ -13,6 +13,12 @@ impl PluginDriver {
       plugins: Default::default(),
     }
   }
+
+  pub fn from_plugins(plugins: Vec<Box<dyn Plugin + Send + Sync>>) -> Self {
+    Self {
+      plugins: Mutex::new(plugins),
+    }
+  }
 }
 
 // Align to https://rollupjs.org/guide/en/#build-hooks
@@ -25,25 +31,19 @@ impl PluginDriver {
       .lock()
       .unwrap()
       .iter_mut()
-      .map(|plugin| plugin.resolve_id(source, importer))
-      .take_while(|result| result.is_some())
-      .map(|r| r.unwrap())
-      .next();
+      .find_map(|plugin| plugin.resolve_id(source, importer));
 
     result
   }
 
   #[inline]
-  pub fn load(&self, id: &str) -> Option<String> {
+  pub fn load(&self, id: &str) -> LoadResult {
     let result = self
       .plugins
       .lock()
       .unwrap()
       .iter_mut()
-      .map(|plugin| plugin.load(id))
-      .take_while(|result| result.is_some())
-      .map(|r| r.unwrap())
-      .next();
+      .find_map(|plugin| plugin.load(id));
 
     result
   }
@@ -65,7 +65,7 @@ pub trait Plugin {
   }
 
   #[inline]
-  fn load(&mut self, _id: &str) -> Option<String> {
+  fn load(&mut self, _id: &str) -> LoadResult {
     // async, first
     None
   }
@@ -1 +1,2 @@
-pub mod node_resolve;
\ No newline at end of file
+pub mod node_resolve;
+pub mod pnp;
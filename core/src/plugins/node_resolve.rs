@@ -1,37 +1,206 @@
-use std::{collections::HashSet, path::Path};
+use std::{collections::HashSet, path::Path, sync::Arc};
 
 use once_cell::sync::Lazy;
+use serde_json::Value;
 
-use crate::{ext::StrExt, plugin_driver::Plugin, types::ResolvedId, utils::is_external_module};
+use crate::{
+  plugin_driver::Plugin,
+  types::{LoadResult, ResolveIdResult, ResolvedId},
+  utils::{
+    is_external_module, is_relative,
+    package_json::{PackageJson, PackageJsonCache},
+    resolve_file_with,
+  },
+};
 
-struct NodeResolver {}
+use super::pnp::{is_pnp_path, PnpManifest, ZipCache};
+
+// Modules mapped to `false` by the `browser` field resolve to this id.
+pub const EMPTY_MODULE_ID: &str = "\0node-resolve:empty.js";
+
+pub struct NodeResolveOptions {
+  // Fields of `package.json` to look for the entry of a package, in order.
+  // Adding `browser` also makes the object form of the `browser` field remap files and modules.
+  pub main_fields: Vec<String>,
+  // Resolve packages through a Yarn Plug'n'Play manifest instead of `node_modules`.
+  pub pnp: Option<PnpManifest>,
+}
+
+impl Default for NodeResolveOptions {
+  fn default() -> Self {
+    Self {
+      main_fields: vec!["module".to_string(), "main".to_string()],
+      pnp: None,
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct NodeResolver {
+  options: NodeResolveOptions,
+  packages: PackageJsonCache,
+  fs: ZipCache,
+}
+
+impl NodeResolver {
+  pub fn new(options: NodeResolveOptions) -> Self {
+    Self {
+      options,
+      packages: Default::default(),
+      fs: Default::default(),
+    }
+  }
+
+  fn resolve_file(&mut self, path: &str) -> Option<String> {
+    let fs = &mut self.fs;
+    resolve_file_with(path, |file| fs.is_file(file))
+  }
+
+  #[inline]
+  fn browser(&self) -> bool {
+    self
+      .options
+      .main_fields
+      .iter()
+      .any(|field| field == "browser")
+  }
+
+  fn find_package(&mut self, dir: &str) -> Option<Arc<PackageJson>> {
+    let fs = &mut self.fs;
+    self
+      .packages
+      .find_up(dir, &mut |path| fs.read_to_string(path))
+  }
+
+  fn resolve_package(&mut self, source: &str, basedir: &str) -> Option<String> {
+    // `@scope/name/sub/path` -> (`@scope/name`, `sub/path`)
+    let mut segments = source.splitn(if source.starts_with('@') { 3 } else { 2 }, '/');
+    let name = if source.starts_with('@') {
+      format!("{}/{}", segments.next()?, segments.next()?)
+    } else {
+      segments.next()?.to_string()
+    };
+    let sub_path = segments.next();
+
+    let package_dir = match &self.options.pnp {
+      Some(pnp) if !pnp.is_ignored(basedir) => pnp.resolve_package_dir(&name, basedir)?,
+      _ => Path::new(basedir).ancestors().find_map(|ancestor| {
+        let dir = ancestor.join("node_modules").join(&name);
+        dir.is_dir().then(|| dir.to_string_lossy().to_string())
+      })?,
+    };
+
+    let fs = &mut self.fs;
+    let package = PackageJson::read(&package_dir, |path| fs.read_to_string(path));
+    let resolved = match sub_path {
+      Some(sub_path) => self.resolve_file(&nodejs_path::resolve!(&package_dir, sub_path)),
+      None => {
+        let main_fields = self.options.main_fields.clone();
+        main_fields
+          .iter()
+          .find_map(|field| {
+            package
+              .as_ref()
+              .and_then(|package| package.get_str(field))
+              .and_then(|entry| self.resolve_file(&nodejs_path::resolve!(&package_dir, entry)))
+          })
+          .or_else(|| self.resolve_file(&nodejs_path::resolve!(&package_dir, "index")))
+      }
+    }?;
+
+    Some(self.remap_file(resolved))
+  }
+
+  // Apply the `browser` field of the package containing `file`.
+  fn remap_file(&mut self, file: String) -> String {
+    if !self.browser() {
+      return file;
+    }
+    let package = match self.find_package(&nodejs_path::dirname(&file)) {
+      Some(package) => package,
+      None => return file,
+    };
+    let mapped = package.browser_map().and_then(|browser_map| {
+      browser_map.iter().find_map(|(key, value)| {
+        if !is_relative(key) {
+          return None;
+        }
+        let key_path = nodejs_path::resolve!(&package.dir, key);
+        (key_path == file || self.resolve_file(&key_path).as_ref() == Some(&file))
+          .then(|| value.clone())
+      })
+    });
+    match mapped {
+      Some(value) => self
+        .resolve_browser_target(&package, &value)
+        .unwrap_or(file),
+      None => file,
+    }
+  }
+
+  fn resolve_browser_target(&mut self, package: &PackageJson, value: &Value) -> Option<String> {
+    match value {
+      Value::Bool(false) => Some(EMPTY_MODULE_ID.to_string()),
+      Value::String(target) if is_external_module(target) => {
+        self.resolve_package(target, &package.dir)
+      }
+      Value::String(target) => self.resolve_file(&nodejs_path::resolve!(&package.dir, target)),
+      _ => None,
+    }
+  }
+}
 
 impl Plugin for NodeResolver {
   fn get_name(&self) -> &'static str {
     "node-resolve"
   }
 
-  fn resolve_id(&mut self, source: &str, importer: Option<&str>) -> crate::types::ResolveIdResult {
-    if importer.is_some() && is_external_module(source) {
-      let result = {
-        let normalized_source = source.replace("node:", "");
-        if BUILTIN_MODULES.contains(normalized_source.as_str()) {
-          ResolvedId::new(normalized_source, true)
-        } else {
-          let raw_id =
-            node_resolve::resolve_from(normalized_source.as_str(), importer.unwrap().as_path());
-          log::debug!("resolving external module {:#?}", normalized_source);
-          match raw_id {
-            Ok(id) => {
-              let file: &Path = id.as_ref();
-              // External should be judged based on `external options`
-              ResolvedId::new(file.to_string_lossy().to_string(), false)
-            }
-            Err(_) => panic!("Module {} is not exist.", normalized_source),
+  fn resolve_id(&mut self, source: &str, importer: Option<&str>) -> ResolveIdResult {
+    let importer = importer?;
+    let importer_dir = nodejs_path::dirname(importer);
+
+    // Modules remapped by the package containing the importer, such as `{ "fs": false }`.
+    if self.browser() && is_external_module(source) {
+      if let Some(package) = self.find_package(&importer_dir) {
+        if let Some(value) = package
+          .browser_map()
+          .and_then(|browser_map| browser_map.get(source))
+          .cloned()
+        {
+          if let Some(id) = self.resolve_browser_target(&package, &value) {
+            return Some(ResolvedId::new(id.into(), false));
           }
         }
-      };
-      Some(result)
+      }
+    }
+
+    if is_external_module(source) {
+      let normalized_source = source.replace("node:", "");
+      if BUILTIN_MODULES.contains(normalized_source.as_str()) {
+        Some(ResolvedId::new(normalized_source.into(), true))
+      } else {
+        log::debug!("resolving external module {:#?}", normalized_source);
+        // External should be judged based on `external options`
+        self
+          .resolve_package(&normalized_source, &importer_dir)
+          .map(|id| ResolvedId::new(id.into(), false))
+      }
+    } else if (self.browser() || is_pnp_path(importer))
+      && (is_relative(source) || nodejs_path::is_absolute(source))
+    {
+      let file = self.resolve_file(&nodejs_path::resolve!(&importer_dir, source))?;
+      let remapped = self.remap_file(file.clone());
+      (remapped != file || is_pnp_path(&remapped)).then(|| ResolvedId::new(remapped.into(), false))
+    } else {
+      None
+    }
+  }
+
+  fn load(&mut self, id: &str) -> LoadResult {
+    if id == EMPTY_MODULE_ID {
+      Some("export default {};".to_string().into())
+    } else if is_pnp_path(id) {
+      self.fs.read_to_string(id).map(Into::into)
     } else {
       None
     }
@@ -0,0 +1,272 @@
+use std::{collections::HashMap, fs::File, io::Read, path::Path};
+
+use fancy_regex::Regex;
+use serde_json::Value;
+use zip::ZipArchive;
+
+// (name, reference). The top-level workspace is `(None, None)`.
+type Locator = (Option<String>, Option<String>);
+
+#[derive(Debug)]
+struct PackageInformation {
+  // Absolute path of the package, without trailing slash.
+  location: String,
+  // `None` for missing peer dependencies.
+  dependencies: HashMap<String, Option<(String, String)>>,
+}
+
+// A Yarn Plug'n'Play manifest, read from `.pnp.data.json` or the state inlined in `.pnp.cjs`.
+#[derive(Debug)]
+pub struct PnpManifest {
+  packages: HashMap<Locator, PackageInformation>,
+  enable_top_level_fallback: bool,
+  fallback_pool: HashMap<String, Option<(String, String)>>,
+  // Paths relative to the manifest matching this pattern are resolved without PnP. It's a JavaScript
+  // regex generated by Yarn, which might contain lookarounds.
+  ignore_pattern: Option<Regex>,
+  dir: String,
+}
+
+impl PnpManifest {
+  pub fn from_file(path: &str) -> anyhow::Result<Self> {
+    let path = nodejs_path::resolve!(path);
+    let source = std::fs::read_to_string(&path)?;
+    let state = if path.ends_with(".json") {
+      source
+    } else {
+      extract_raw_runtime_state(&source)
+        .ok_or_else(|| anyhow::anyhow!("Cannot find `RAW_RUNTIME_STATE` in {}", path))?
+    };
+    Self::from_json(&nodejs_path::dirname(&path), &serde_json::from_str(&state)?)
+  }
+
+  fn from_json(dir: &str, json: &Value) -> anyhow::Result<Self> {
+    let registry = json
+      .get("packageRegistryData")
+      .and_then(Value::as_array)
+      .ok_or_else(|| anyhow::anyhow!("Invalid PnP manifest: missing `packageRegistryData`"))?;
+
+    let mut packages = HashMap::default();
+    registry
+      .iter()
+      .filter_map(Value::as_array)
+      .for_each(|entry| {
+        let name = entry.get(0).and_then(Value::as_str).map(|s| s.to_string());
+        let references = entry.get(1).and_then(Value::as_array);
+        references
+          .into_iter()
+          .flatten()
+          .filter_map(Value::as_array)
+          .for_each(|entry| {
+            let reference = entry.get(0).and_then(Value::as_str).map(|s| s.to_string());
+            if let Some(info) = entry.get(1) {
+              let location = info
+                .get("packageLocation")
+                .and_then(Value::as_str)
+                .unwrap_or("./");
+              let dependencies = info
+                .get("packageDependencies")
+                .and_then(Value::as_array)
+                .map(|dependencies| parse_dependencies(dependencies))
+                .unwrap_or_default();
+              packages.insert(
+                (name.clone(), reference),
+                PackageInformation {
+                  location: nodejs_path::resolve!(dir, location),
+                  dependencies,
+                },
+              );
+            }
+          });
+      });
+
+    Ok(Self {
+      packages,
+      enable_top_level_fallback: json
+        .get("enableTopLevelFallback")
+        .and_then(Value::as_bool)
+        .unwrap_or(false),
+      fallback_pool: json
+        .get("fallbackPool")
+        .and_then(Value::as_array)
+        .map(|pool| parse_dependencies(pool))
+        .unwrap_or_default(),
+      ignore_pattern: json
+        .get("ignorePatternData")
+        .and_then(Value::as_str)
+        .map(Regex::new)
+        .transpose()?,
+      dir: dir.to_string(),
+    })
+  }
+
+  // `./ignored/` for `<manifest dir>/ignored`, just like Yarn matches `ignorePatternData`.
+  pub fn is_ignored(&self, path: &str) -> bool {
+    self.ignore_pattern.as_ref().map_or(false, |pattern| {
+      let relative = nodejs_path::relative(&self.dir, path);
+      let relative = if relative.starts_with("../") {
+        relative
+      } else {
+        format!("./{}", relative)
+      };
+      let relative = if relative.ends_with('/') {
+        relative
+      } else {
+        format!("{}/", relative)
+      };
+      pattern.is_match(&relative).unwrap_or(false)
+    })
+  }
+
+  // The package owning `path`, which is the one with the deepest location containing it.
+  fn find_issuer(&self, path: &str) -> Option<&PackageInformation> {
+    self
+      .packages
+      .values()
+      .filter(|package| {
+        path == package.location
+          || (path.starts_with(&package.location)
+            && path[package.location.len()..].starts_with('/'))
+      })
+      .max_by_key(|package| package.location.len())
+  }
+
+  // Directory of the package named `name` as seen from `importer_dir`.
+  pub fn resolve_package_dir(&self, name: &str, importer_dir: &str) -> Option<String> {
+    let issuer = self.find_issuer(importer_dir)?;
+    let dependency = match issuer.dependencies.get(name) {
+      Some(dependency) => dependency.clone(),
+      None if self.enable_top_level_fallback => self
+        .packages
+        .get(&(None, None))
+        .and_then(|top_level| top_level.dependencies.get(name))
+        .or_else(|| self.fallback_pool.get(name))
+        .cloned()
+        .flatten(),
+      None => None,
+    };
+    let (name, reference) = dependency?;
+    self
+      .packages
+      .get(&(Some(name), Some(reference)))
+      .map(|package| package.location.clone())
+  }
+}
+
+// `[name, reference]` pairs, where `reference` is `[alias, reference]` for aliased dependencies.
+fn parse_dependencies(dependencies: &[Value]) -> HashMap<String, Option<(String, String)>> {
+  dependencies
+    .iter()
+    .filter_map(Value::as_array)
+    .filter_map(|pair| {
+      let name = pair.get(0)?.as_str()?;
+      let target = match pair.get(1)? {
+        Value::String(reference) => Some((name.to_string(), reference.clone())),
+        Value::Array(alias) => Some((
+          alias.get(0)?.as_str()?.to_string(),
+          alias.get(1)?.as_str()?.to_string(),
+        )),
+        _ => None,
+      };
+      Some((name.to_string(), target))
+    })
+    .collect()
+}
+
+// `.pnp.cjs` inlines the manifest as `const RAW_RUNTIME_STATE = '{...}';`.
+fn extract_raw_runtime_state(source: &str) -> Option<String> {
+  let start = source.find("RAW_RUNTIME_STATE =")?;
+  let rest = &source[start..];
+  let mut chars = rest[rest.find('\'')? + 1..].chars();
+  let mut state = String::new();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next()? {
+        'n' => state.push('\n'),
+        escaped => state.push(escaped),
+      },
+      '\'' => return Some(state),
+      c => state.push(c),
+    }
+  }
+  None
+}
+
+// Splits `/repo/.yarn/cache/pkg-npm-1.0.0-hash.zip/node_modules/pkg/index.js` into the archive and the entry.
+pub fn split_zip_path(path: &str) -> Option<(&str, &str)> {
+  path
+    .find(".zip/")
+    .map(|index| (&path[..index + 4], &path[index + 5..]))
+}
+
+// Yarn instantiates packages with peer dependencies once per set of peers, at virtual locations such
+// as `/repo/.yarn/__virtual__/pkg-virtual-<hash>/<n>/<subpath>`. The real path is `<subpath>`
+// resolved from the `n`th parent of `/repo/.yarn`.
+pub fn resolve_virtual_path(path: &str) -> String {
+  let index = match path.find("/__virtual__/") {
+    Some(index) => index,
+    None => return path.to_string(),
+  };
+  let mut segments = path[index + "/__virtual__/".len()..].splitn(3, '/');
+  let _hash = segments.next();
+  let depth = match segments
+    .next()
+    .and_then(|depth| depth.parse::<usize>().ok())
+  {
+    Some(depth) => depth,
+    None => return path.to_string(),
+  };
+  let base = (0..depth).fold(path[..index].to_string(), |dir, _| {
+    nodejs_path::dirname(&dir)
+  });
+  let subpath = segments.next().filter(|subpath| !subpath.is_empty());
+  resolve_virtual_path(&nodejs_path::resolve!(&base, subpath.unwrap_or(".")))
+}
+
+// Files inside zip archives or at virtual locations can't be found by the default resolution.
+pub fn is_pnp_path(path: &str) -> bool {
+  split_zip_path(path).is_some() || path.contains("/__virtual__/")
+}
+
+// Reads files from the disk and the zip archives of the Yarn cache, through virtual locations as well.
+#[derive(Default)]
+pub struct ZipCache {
+  archives: HashMap<String, Option<ZipArchive<File>>>,
+}
+
+impl ZipCache {
+  fn archive(&mut self, zip_path: &str) -> Option<&mut ZipArchive<File>> {
+    self
+      .archives
+      .entry(zip_path.to_string())
+      .or_insert_with(|| {
+        File::open(zip_path)
+          .ok()
+          .and_then(|file| ZipArchive::new(file).ok())
+      })
+      .as_mut()
+  }
+
+  pub fn is_file(&mut self, path: &str) -> bool {
+    let path = resolve_virtual_path(path);
+    match split_zip_path(&path) {
+      Some((zip_path, entry)) => self.archive(zip_path).map_or(false, |archive| {
+        archive.by_name(entry).map_or(false, |file| file.is_file())
+      }),
+      None => Path::new(&path).is_file(),
+    }
+  }
+
+  pub fn read_to_string(&mut self, path: &str) -> Option<String> {
+    let path = resolve_virtual_path(path);
+    match split_zip_path(&path) {
+      Some((zip_path, entry)) => {
+        let mut file = self.archive(zip_path)?.by_name(entry).ok()?;
+        let mut source = String::new();
+        file.read_to_string(&mut source).ok()?;
+        Some(source)
+      }
+      None => std::fs::read_to_string(&path).ok(),
+    }
+  }
+}
@@ -1,5 +1,5 @@
 use swc_atoms::JsWord;
-use swc_ecma_ast::{Ident, ObjectPatProp, Pat};
+use swc_ecma_ast::{Expr, Ident, Lit, MemberProp, ObjectPatProp, Pat};
 
 #[inline]
 pub fn collect_ident_of_pat(pat: &Pat) -> Vec<&Ident> {
@@ -61,3 +61,16 @@ pub fn collect_js_word_of_pat(pat: &Pat) -> Vec<JsWord> {
     .map(|id| id.sym.clone())
     .collect()
 }
+
+// `foo.bar` and `foo['bar']` => `Some("bar")`
+#[inline]
+pub fn static_member_prop(prop: &MemberProp) -> Option<JsWord> {
+  match prop {
+    MemberProp::Ident(ident) => Some(ident.sym.clone()),
+    MemberProp::Computed(computed) => match computed.expr.as_ref() {
+      Expr::Lit(Lit::Str(str)) => Some(str.value.clone()),
+      _ => None,
+    },
+    MemberProp::PrivateName(_) => None,
+  }
+}
@@ -2,28 +2,35 @@ use crossbeam::channel::Sender;
 use linked_hash_map::LinkedHashMap;
 use std::{
   collections::{HashMap, HashSet},
-  sync::{Arc, Mutex},
+  sync::Arc,
 };
 use swc_atoms::JsWord;
-use swc_common::Mark;
+use swc_common::{comments::SingleThreadedComments, Mark};
 use swc_ecma_ast::{
-  ArrowExpr, BindingIdent, BlockStmt, BlockStmtOrExpr, CallExpr, CatchClause, ClassDecl, ClassExpr,
-  ClassMethod, ClassProp, Constructor, Decl, DefaultDecl, ExportDefaultDecl, Expr, FnDecl, FnExpr,
-  ForInStmt, ForOfStmt, ForStmt, Function, Ident, ImportDecl, ImportNamedSpecifier, MemberExpr,
-  MethodProp, ModuleDecl, ModuleItem, ObjectLit, Param, Pat, PatOrExpr, PrivateMethod, SetterProp,
-  Stmt, TaggedTpl, Tpl, VarDecl, VarDeclarator,
+  ArrowExpr, BindingIdent, BlockStmt, BlockStmtOrExpr, CallExpr, Callee, CatchClause, Class,
+  ClassDecl, ClassExpr, ClassMethod, ClassProp, Constructor, Decl, DefaultDecl, ExportDefaultDecl,
+  ExportNamedSpecifier, Expr, FnDecl, FnExpr, ForInStmt, ForOfStmt, ForStmt, Function, GetterProp,
+  Ident, ImportDecl, ImportNamedSpecifier, MemberExpr, MethodProp, ModuleDecl, ModuleItem,
+  ObjectLit, Param, Pat, PatOrExpr, PrivateMethod, PropName, SetterProp, Stmt, TaggedTpl, ThisExpr,
+  Tpl, VarDecl, VarDeclarator,
 };
 use swc_ecma_visit::{noop_visit_mut_type, VisitMut, VisitMutWith};
 
 use crate::{
+  error::BuildError,
   ext::MarkExt,
   graph::Msg,
-  symbol_box::SymbolBox,
-  utils::side_effect::{detect_side_effect, SideEffect},
-  worker::RolldownError,
+  symbol_box::LocalMarks,
+  types::NormalizedInputOptions,
+  utils::{
+    define::DefineReplacer,
+    side_effect::{collect_no_side_effect_fns, detect_side_effect, SideEffect},
+  },
+  warning::{Warning, WarningCode},
 };
 
 use self::{
+  helper::static_member_prop,
   rel::RelationInfo,
   scope::{BindType, Scope, ScopeKind},
 };
@@ -40,6 +47,10 @@ pub struct ModuleItemInfo {
   pub reads: HashSet<Mark>,
   pub writes: HashSet<Mark>,
   pub side_effect: Option<SideEffect>,
+  // Functions declared in the bundle which are called by the statement.
+  pub callees: HashSet<Mark>,
+  // `foo.bar` => `(foo, bar)`. `foo` is not in `reads` unless it's used in other ways.
+  pub member_reads: HashSet<(Mark, JsWord)>,
   // TODO: should we place it here?
   pub export_mark: Option<Mark>,
 }
@@ -62,12 +73,28 @@ pub struct Scanner {
   pub re_export_infos: LinkedHashMap<JsWord, RelationInfo>,
   pub export_all_sources: HashSet<(JsWord, usize)>,
   pub dynamic_imports: HashSet<DynImportDesc>,
-  pub symbol_box: Arc<Mutex<SymbolBox>>,
+  pub marks: LocalMarks,
   pub tx: Sender<Msg>,
+  pub input_options: Arc<NormalizedInputOptions>,
+  pub comments: SingleThreadedComments,
+  // Top-level functions annotated with `/*#__NO_SIDE_EFFECTS__*/`.
+  pub no_side_effect_fns: HashSet<Mark>,
+  // Names which are not declared in the module, such as `console`.
+  pub global_reads: HashSet<JsWord>,
+  pub warnings: Vec<Warning>,
+  pub errors: Vec<BuildError>,
+  // Marks of bindings declared or imported at the top level.
+  top_level_marks: HashSet<Mark>,
+  // Number of enclosing functions and classes, which have their own `this`.
+  this_scope_depth: usize,
 }
 
 impl Scanner {
-  pub fn new(symbol_box: Arc<Mutex<SymbolBox>>, tx: Sender<Msg>) -> Self {
+  pub fn new(
+    tx: Sender<Msg>,
+    input_options: Arc<NormalizedInputOptions>,
+    comments: SingleThreadedComments,
+  ) -> Self {
     Self {
       cur_relation_order: 0,
       statement_infos: Default::default(),
@@ -81,12 +108,20 @@ impl Scanner {
       dynamic_imports: Default::default(),
       import_infos: Default::default(),
       ident_type: IdentType::Ref,
-      symbol_box,
+      marks: Default::default(),
       tx,
+      input_options,
+      comments,
+      no_side_effect_fns: Default::default(),
+      global_reads: Default::default(),
+      warnings: Default::default(),
+      errors: Default::default(),
+      top_level_marks: Default::default(),
+      this_scope_depth: 0,
     }
   }
 
-  pub fn declare(&mut self, id: &mut Ident, kind: BindType) -> Result<(), anyhow::Error> {
+  pub fn declare(&mut self, id: &mut Ident, kind: BindType) {
     let is_var_decl = matches!(kind, BindType::Var);
     let found_scope = self.stacks.iter_mut().enumerate().rev().find(|(_, scope)| {
       if is_var_decl {
@@ -109,11 +144,7 @@ impl Scanner {
         );
       }
 
-      let mark = self
-        .symbol_box
-        .lock()
-        .map_err(|_| RolldownError::Lock)?
-        .new_mark();
+      let mark = self.marks.new_mark();
 
       log::debug!(
         "[scanner]: new mark {:?} for `{}` is_root_scope: {:#}",
@@ -130,6 +161,7 @@ impl Scanner {
 
       let module_item_info = &mut self.statement_infos[self.cur_stmt_index];
       if is_root_scope {
+        self.top_level_marks.insert(mark);
         // TODO: duplicate detect
         module_item_info
           .declared
@@ -137,29 +169,41 @@ impl Scanner {
           .or_insert_with(|| mark);
       };
     }
-    Ok(())
   }
 
   pub fn resolve_ctxt_for_ident(&mut self, ident: &mut Ident) {
-    let mut _is_finded = false;
-    for (idx, scope) in &mut self.stacks.iter_mut().enumerate().rev() {
-      let is_root_scope = idx == 0;
-      if let Some(mark) = scope.declared_symbols.get(&ident.sym) {
+    if let Some(mark) = self.resolve_ctxt(ident) {
+      let stmt_info = &mut self.statement_infos[self.cur_stmt_index];
+      // TODO: duplicate detect
+      stmt_info.reads.insert(mark);
+    }
+  }
+
+  // Returns the mark if the ident refers to a top-level binding.
+  fn resolve_ctxt(&mut self, ident: &mut Ident) -> Option<Mark> {
+    let found = self
+      .stacks
+      .iter()
+      .enumerate()
+      .rev()
+      .find_map(|(idx, scope)| {
+        scope
+          .declared_symbols
+          .get(&ident.sym)
+          .map(|mark| (idx == 0, *mark))
+      });
+    match found {
+      Some((is_root_scope, mark)) => {
         ident.span.ctxt = mark.as_ctxt();
-        _is_finded = true;
-        if is_root_scope {
-          let stmt_info = &mut self.statement_infos[self.cur_stmt_index];
-          // TODO: duplicate detect
-          stmt_info.reads.insert(*mark);
-        }
-        break;
-      };
+        is_root_scope.then(|| mark)
+      }
+      // The ident keeps the empty ctxt, which the side effect detector relies on to find global
+      // accesses.
+      None => {
+        self.global_reads.insert(ident.sym.clone());
+        None
+      }
     }
-    // TODO: should we add this as it does not reflect in the bundle itself, or maybe we can do it elsewhere.
-    // if !is_finded {
-    //   let stmt_info = &mut self.statement_infos[self.cur_stmt_index];
-    //   stmt_info.reads.insert(ident.sym.clone());
-    // }
   }
 
   fn visit_mut_stmt_within_child_scope(&mut self, s: &mut Stmt) {
@@ -198,14 +242,31 @@ impl VisitMut for Scanner {
 
   fn visit_mut_module_item(&mut self, node: &mut swc_ecma_ast::ModuleItem) {
     node.visit_mut_children_with(self);
-    self.statement_infos[self.cur_stmt_index].side_effect = detect_side_effect(node);
+    if !self.input_options.define.is_empty() {
+      node.visit_mut_with(&mut DefineReplacer::new(&self.input_options.define));
+    }
+    // Side effects only matter to tree-shaking.
+    if let Some(treeshake) = &self.input_options.treeshake {
+      let stmt_info = &mut self.statement_infos[self.cur_stmt_index];
+      stmt_info.side_effect = detect_side_effect(
+        node,
+        treeshake,
+        &self.comments,
+        &self.top_level_marks,
+        &mut stmt_info.callees,
+      );
+      if treeshake.annotations {
+        self
+          .no_side_effect_fns
+          .extend(collect_no_side_effect_fns(node, treeshake, &self.comments));
+      }
+    }
     self.cur_stmt_index += 1;
   }
 
   fn visit_mut_module_decl(&mut self, node: &mut ModuleDecl) {
-    self.add_import(node);
-    if let Err(e) = self.add_export(node) {
-      eprintln!("{}", e);
+    if let Err(err) = self.add_import(node).and_then(|_| self.add_export(node)) {
+      self.errors.push(err);
     }
 
     node.visit_mut_children_with(self);
@@ -214,6 +275,25 @@ impl VisitMut for Scanner {
   fn visit_mut_call_expr(&mut self, node: &mut CallExpr) {
     self.add_dynamic_import(node);
 
+    if let Callee::Expr(callee) = &node.callee {
+      if let Expr::Ident(ident) = callee.as_ref() {
+        let is_global_eval = &*ident.sym == "eval"
+          && !self
+            .stacks
+            .iter()
+            .any(|scope| scope.declared_symbols.contains_key(&ident.sym));
+        if is_global_eval {
+          self.warnings.push(
+            Warning::new(
+              WarningCode::Eval,
+              "Use of eval is strongly discouraged as it poses security risks and may cause issues with minification",
+            )
+            .with_span(node.span),
+          );
+        }
+      }
+    }
+
     node.visit_mut_children_with(self);
   }
 
@@ -274,10 +354,17 @@ impl VisitMut for Scanner {
     self.pop_scope();
   }
 
+  fn visit_mut_class(&mut self, n: &mut Class) {
+    n.decorators.visit_mut_with(self);
+    n.super_class.visit_mut_with(self);
+
+    self.this_scope_depth += 1;
+    n.body.visit_mut_with(self);
+    self.this_scope_depth -= 1;
+  }
+
   fn visit_mut_class_decl(&mut self, n: &mut ClassDecl) {
-    if let Err(e) = self.declare(&mut n.ident, BindType::Let) {
-      eprintln!("{}", e);
-    }
+    self.declare(&mut n.ident, BindType::Let);
 
     self.push_scope(ScopeKind::Fn);
 
@@ -378,6 +465,11 @@ impl VisitMut for Scanner {
     self.ident_type = old;
   }
 
+  fn visit_mut_export_named_specifier(&mut self, n: &mut ExportNamedSpecifier) {
+    // The exported name doesn't reference a binding.
+    n.orig.visit_mut_with(self);
+  }
+
   fn visit_mut_fn_decl(&mut self, node: &mut FnDecl) {
     self.push_scope(ScopeKind::Fn);
 
@@ -390,9 +482,7 @@ impl VisitMut for Scanner {
     self.push_scope(ScopeKind::Fn);
 
     if let Some(ident) = &mut e.ident {
-      if let Err(e) = self.declare(ident, BindType::Var) {
-        eprintln!("{}", e);
-      }
+      self.declare(ident, BindType::Var);
     }
     e.function.visit_mut_with(self);
 
@@ -438,6 +528,7 @@ impl VisitMut for Scanner {
     self.ident_type = IdentType::Ref;
     f.decorators.visit_mut_with(self);
 
+    self.this_scope_depth += 1;
     self.ident_type = IdentType::Binding(BindType::Var);
     f.params.visit_mut_with(self);
 
@@ -449,14 +540,21 @@ impl VisitMut for Scanner {
       }
       None => {}
     }
+    self.this_scope_depth -= 1;
+  }
+
+  fn visit_mut_getter_prop(&mut self, n: &mut GetterProp) {
+    n.key.visit_mut_with(self);
+
+    self.this_scope_depth += 1;
+    n.body.visit_mut_with(self);
+    self.this_scope_depth -= 1;
   }
 
   fn visit_mut_ident(&mut self, i: &mut Ident) {
     match self.ident_type {
       IdentType::Binding(kind) => {
-        if let Err(e) = self.declare(i, kind) {
-          eprintln!("{}", e);
-        }
+        self.declare(i, kind);
       }
       IdentType::Ref => {
         self.resolve_ctxt_for_ident(i);
@@ -475,7 +573,16 @@ impl VisitMut for Scanner {
 
   /// Leftmost one of a member expression should be resolved.
   fn visit_mut_member_expr(&mut self, e: &mut MemberExpr) {
-    e.obj.visit_mut_with(self);
+    match (e.obj.as_mut(), static_member_prop(&e.prop)) {
+      // `foo.bar` is a reference to the export `bar` if `foo` turns out to be a namespace.
+      (Expr::Ident(obj), Some(prop)) if self.ident_type == IdentType::Ref => {
+        if let Some(mark) = self.resolve_ctxt(obj) {
+          let stmt_info = &mut self.statement_infos[self.cur_stmt_index];
+          stmt_info.member_reads.insert((mark, prop));
+        }
+      }
+      _ => e.obj.visit_mut_with(self),
+    }
 
     if e.prop.is_computed() {
       e.prop.visit_mut_with(self);
@@ -529,13 +636,22 @@ impl VisitMut for Scanner {
 
   // fn visit_mut_private_name(&mut self, _: &mut PrivateName) {}
 
+  fn visit_mut_prop_name(&mut self, n: &mut PropName) {
+    // Only computed keys reference bindings.
+    if let PropName::Computed(computed) = n {
+      computed.visit_mut_with(self);
+    }
+  }
+
   fn visit_mut_setter_prop(&mut self, n: &mut SetterProp) {
     n.key.visit_mut_with(self);
 
     self.push_scope(ScopeKind::Fn);
+    self.this_scope_depth += 1;
     self.ident_type = IdentType::Binding(BindType::Var);
     n.param.visit_mut_with(self);
     n.body.visit_mut_with(self);
+    self.this_scope_depth -= 1;
     self.pop_scope();
   }
 
@@ -543,6 +659,18 @@ impl VisitMut for Scanner {
     stmts.visit_mut_children_with(self)
   }
 
+  fn visit_mut_this_expr(&mut self, n: &mut ThisExpr) {
+    if self.this_scope_depth == 0 {
+      self.warnings.push(
+        Warning::new(
+          WarningCode::ThisIsUndefined,
+          "The 'this' keyword is equivalent to 'undefined' at the top level of an ES module",
+        )
+        .with_span(n.span),
+      );
+    }
+  }
+
   fn visit_mut_var_decl(&mut self, decl: &mut VarDecl) {
     let ident_type = self.ident_type;
     self.ident_type = IdentType::Binding(decl.kind.into());
@@ -647,9 +775,7 @@ impl<'me> VisitMut for Hoister<'me> {
 
   fn visit_mut_ident(&mut self, i: &mut Ident) {
     if let Some(IdentType::Binding(kind)) = &self.ident_type {
-      if let Err(e) = self.scanner.declare(i, *kind) {
-        eprintln!("{}", e);
-      }
+      self.scanner.declare(i, *kind);
     }
   }
 
@@ -658,9 +784,7 @@ impl<'me> VisitMut for Hoister<'me> {
     if self.catch_param_decls.contains(&node.ident.sym) {
       return;
     }
-    if let Err(e) = self.scanner.declare(&mut node.ident, BindType::Var) {
-      eprintln!("{}", e);
-    }
+    self.scanner.declare(&mut node.ident, BindType::Var);
   }
 
   // fn visit_mut_assign_pat_prop(&mut self, node: &mut AssignPatProp) {
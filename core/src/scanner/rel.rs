@@ -1,12 +1,16 @@
 use std::collections::HashSet;
 
 use swc_atoms::JsWord;
-use swc_common::Mark;
+use swc_common::{Mark, Span};
 use swc_ecma_ast::{
   CallExpr, Callee, Decl, DefaultDecl, ExportSpecifier, Expr, Lit, ModuleDecl, ModuleExportName,
 };
 
-use crate::{ext::SyntaxContextExt, graph::Rel, worker::RolldownError};
+use crate::{
+  error::{BuildError, ErrorCode},
+  ext::SyntaxContextExt,
+  graph::Rel,
+};
 
 use super::{helper::collect_js_word_of_pat, Scanner};
 
@@ -16,6 +20,7 @@ pub struct ExportDesc {
   pub identifier: Option<JsWord>,
   pub local_name: JsWord,
   pub mark: Mark,
+  pub span: Span,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
@@ -35,7 +40,7 @@ pub struct DynImportDesc {
 }
 
 impl Scanner {
-  pub fn add_import(&mut self, module_decl: &mut ModuleDecl) {
+  pub fn add_import(&mut self, module_decl: &mut ModuleDecl) -> Result<(), BuildError> {
     if let ModuleDecl::Import(import_decl) = module_decl {
       let source = &import_decl.src.value;
       let import_info = self.import_infos.entry(source.clone()).or_insert_with(|| {
@@ -45,47 +50,52 @@ impl Scanner {
       });
 
       // We separate each specifier to support later tree-shaking.
-      import_decl.specifiers.iter_mut().for_each(|specifier| {
-        let used;
-        let original;
-        let mark;
-        match specifier {
-          // import foo from './foo'
-          swc_ecma_ast::ImportSpecifier::Default(n) => {
-            used = n.local.sym.clone();
-            original = "default".into();
-            mark = n.local.span.ctxt.as_mark();
-          }
-          // import { foo } from './foo'
-          // import { foo as foo2 } from './foo'
-          swc_ecma_ast::ImportSpecifier::Named(n) => {
-            used = n.local.sym.clone();
-            original = n
-              .imported // => foo2 in `import { foo as foo2 } from './foo'`
-              .as_ref()
-              .map_or(used.clone(), |module_export_name| {
-                if let ModuleExportName::Ident(ident) = module_export_name {
-                  ident.sym.clone()
-                } else {
-                  panic!("")
-                }
-              });
-            mark = n.local.span.ctxt.as_mark();
-          }
-          // import * as foo from './foo'
-          swc_ecma_ast::ImportSpecifier::Namespace(n) => {
-            used = n.local.sym.clone();
-            original = "*".into();
-            mark = n.local.span.ctxt.as_mark();
+      import_decl
+        .specifiers
+        .iter_mut()
+        .try_for_each(|specifier| {
+          let used;
+          let original;
+          let mark;
+          let span;
+          match specifier {
+            // import foo from './foo'
+            swc_ecma_ast::ImportSpecifier::Default(n) => {
+              used = n.local.sym.clone();
+              original = "default".into();
+              mark = n.local.span.ctxt.as_mark();
+              span = n.span;
+            }
+            // import { foo } from './foo'
+            // import { foo as foo2 } from './foo'
+            swc_ecma_ast::ImportSpecifier::Named(n) => {
+              used = n.local.sym.clone();
+              original = match &n.imported {
+                // => foo2 in `import { foo as foo2 } from './foo'`
+                Some(module_export_name) => get_sym_from_module_export(module_export_name)?,
+                None => used.clone(),
+              };
+              mark = n.local.span.ctxt.as_mark();
+              span = n.span;
+            }
+            // import * as foo from './foo'
+            swc_ecma_ast::ImportSpecifier::Namespace(n) => {
+              used = n.local.sym.clone();
+              original = "*".into();
+              mark = n.local.span.ctxt.as_mark();
+              span = n.span;
+            }
           }
-        }
-        import_info.names.insert(Specifier {
-          original,
-          used,
-          mark,
-        });
-      });
+          import_info.names.insert(Specifier {
+            original,
+            used,
+            mark,
+            span,
+          });
+          Ok(())
+        })?;
     }
+    Ok(())
   }
 
   pub fn add_dynamic_import(&mut self, call_exp: &CallExpr) {
@@ -96,19 +106,18 @@ impl Scanner {
         .get(0)
         .map(|exp_or_spread| &exp_or_spread.expr)
       {
+        // Just like Rollup, dynamic imports with non-literal arguments, such as `import(url)`, are kept as they are.
         if let Expr::Lit(Lit::Str(first_param)) = exp.as_ref() {
           self.dynamic_imports.insert(DynImportDesc {
             argument: first_param.value.clone(),
             id: None,
           });
-        } else {
-          panic!("unkown dynamic import params")
         }
       }
     }
   }
 
-  pub fn add_export(&mut self, module_decl: &ModuleDecl) -> Result<(), anyhow::Error> {
+  pub fn add_export(&mut self, module_decl: &ModuleDecl) -> Result<(), BuildError> {
     match module_decl {
       ModuleDecl::ExportDefaultDecl(node) => {
         let identifier = match &node.decl {
@@ -116,11 +125,7 @@ impl Scanner {
           DefaultDecl::Fn(node) => node.ident.as_ref().map(|id| id.sym.clone()),
           _ => None,
         };
-        let mark = self
-          .symbol_box
-          .lock()
-          .map_err(|_| RolldownError::Lock)?
-          .new_mark();
+        let mark = self.marks.new_mark();
         self.statement_infos[self.cur_stmt_index].export_mark = Some(mark);
         self.local_exports.insert(
           "default".into(),
@@ -128,6 +133,7 @@ impl Scanner {
             identifier,
             local_name: "default".into(),
             mark,
+            span: node.span,
           },
         );
       }
@@ -137,11 +143,7 @@ impl Scanner {
           Expr::Ident(id) => Some(id.sym.clone()),
           _ => None,
         };
-        let mark = self
-          .symbol_box
-          .lock()
-          .map_err(|_| RolldownError::Lock)?
-          .new_mark();
+        let mark = self.marks.new_mark();
         self.statement_infos[self.cur_stmt_index].export_mark = Some(mark);
         self.local_exports.insert(
           "default".into(),
@@ -149,6 +151,7 @@ impl Scanner {
             identifier,
             local_name: "default".into(),
             mark,
+            span: node.span,
           },
         );
       }
@@ -173,27 +176,23 @@ impl Scanner {
                     });
                 // export { name } from './other'
                 let source = source_node.value.clone();
-                let name = s
-                  .exported
-                  .as_ref()
-                  .map_or(get_sym_from_module_export(&s.orig), |id| {
-                    get_sym_from_module_export(id)
-                  });
-                let re_export_mark = self
-                  .symbol_box
-                  .lock()
-                  .map_err(|_| RolldownError::Lock)?
-                  .new_mark();
+                let original = get_sym_from_module_export(&s.orig)?;
+                let name = match &s.exported {
+                  Some(exported) => get_sym_from_module_export(exported)?,
+                  None => original.clone(),
+                };
+                let re_export_mark = self.marks.new_mark();
                 re_export_info.names.insert(Specifier {
-                  original: get_sym_from_module_export(&s.orig),
+                  original: original.clone(),
                   used: name.clone(),
                   mark: re_export_mark,
+                  span: s.span,
                 });
                 self.statement_infos[self.cur_stmt_index].export_mark = Some(re_export_mark);
                 self.re_exports.insert(
                   name.clone(),
                   ReExportDesc {
-                    local_name: get_sym_from_module_export(&s.orig),
+                    local_name: original,
                     source,
                     original: name,
                     mark: re_export_mark,
@@ -202,15 +201,13 @@ impl Scanner {
               } else {
                 // export { foo, bar, baz }
                 log::debug!("export var {:#?}", s);
-                let local_name = get_sym_from_module_export(&s.orig);
-                let exported_name: JsWord = s
-                  .exported
-                  .as_ref()
-                  .map_or(get_sym_from_module_export(&s.orig), |id| {
-                    get_sym_from_module_export(id)
-                  });
+                let local_name = get_sym_from_module_export(&s.orig)?;
+                let exported_name = match &s.exported {
+                  Some(exported) => get_sym_from_module_export(exported)?,
+                  None => local_name.clone(),
+                };
 
-                let mark = self.symbol_box.lock().unwrap().new_mark();
+                let mark = self.marks.new_mark();
                 self.statement_infos[self.cur_stmt_index].export_mark = Some(mark);
                 self.local_exports.insert(
                   exported_name,
@@ -218,12 +215,19 @@ impl Scanner {
                     identifier: None,
                     local_name,
                     mark,
+                    span: s.span,
                   },
                 );
               };
             }
             ExportSpecifier::Namespace(s) => {
-              let source = node.src.as_ref().map(|str| str.value.clone()).unwrap();
+              // export * as name from './other'
+              let source = match &node.src {
+                Some(source) => source.value.clone(),
+                // The parser only accepts `export * as name` with a source.
+                None => return Ok(()),
+              };
+              let name = get_sym_from_module_export(&s.name)?;
               let re_export_info =
                 self
                   .re_export_infos
@@ -237,19 +241,14 @@ impl Scanner {
                     self.cur_relation_order += 1;
                     rel
                   });
-              let re_export_mark = self
-                .symbol_box
-                .lock()
-                .map_err(|_| RolldownError::Lock)?
-                .new_mark();
+              let re_export_mark = self.marks.new_mark();
 
               re_export_info.names.insert(Specifier {
                 original: "*".into(),
-                used: get_sym_from_module_export(&s.name),
+                used: name.clone(),
                 mark: re_export_mark,
+                span: s.span,
               });
-              // export * as name from './other'
-              let name = get_sym_from_module_export(&s.name);
               self.statement_infos[self.cur_stmt_index].export_mark = Some(re_export_mark);
               self.re_exports.insert(
                 name.clone(),
@@ -266,7 +265,7 @@ impl Scanner {
               // Rollup doesn't support it.
             }
           };
-          Ok::<(), RolldownError>(())
+          Ok(())
         })?;
       }
       ModuleDecl::ExportDecl(node) => {
@@ -274,11 +273,7 @@ impl Scanner {
           Decl::Class(node) => {
             // export class Foo {}
             let local_name = node.ident.sym.clone();
-            let mark = self
-              .symbol_box
-              .lock()
-              .map_err(|_| RolldownError::Lock)?
-              .new_mark();
+            let mark = self.marks.new_mark();
             self.statement_infos[self.cur_stmt_index].export_mark = Some(mark);
             self.local_exports.insert(
               local_name.clone(),
@@ -286,17 +281,14 @@ impl Scanner {
                 identifier: None,
                 local_name,
                 mark,
+                span: node.ident.span,
               },
             );
           }
           Decl::Fn(node) => {
             // export function foo () {}
             let local_name = node.ident.sym.clone();
-            let mark = self
-              .symbol_box
-              .lock()
-              .map_err(|_| RolldownError::Lock)?
-              .new_mark();
+            let mark = self.marks.new_mark();
             self.statement_infos[self.cur_stmt_index].export_mark = Some(mark);
             self.local_exports.insert(
               local_name.clone(),
@@ -304,21 +296,18 @@ impl Scanner {
                 identifier: None,
                 local_name,
                 mark,
+                span: node.ident.span,
               },
             );
           }
           Decl::Var(node) => {
             // export var { foo, bar } = ...
             // export var foo = 1, bar = 2;
-            node.decls.iter().try_for_each(|decl| {
+            node.decls.iter().for_each(|decl| {
               collect_js_word_of_pat(&decl.name)
                 .into_iter()
-                .try_for_each(|local_name| {
-                  let mark = self
-                    .symbol_box
-                    .lock()
-                    .map_err(|_| RolldownError::Lock)?
-                    .new_mark();
+                .for_each(|local_name| {
+                  let mark = self.marks.new_mark();
                   self.statement_infos[self.cur_stmt_index].export_mark = Some(mark);
                   self.local_exports.insert(
                     local_name.clone(),
@@ -326,11 +315,11 @@ impl Scanner {
                       identifier: None,
                       local_name,
                       mark,
+                      span: decl.span,
                     },
                   );
-                  Ok::<(), RolldownError>(())
                 })
-            })?;
+            });
           }
           _ => {}
         }
@@ -355,6 +344,7 @@ pub struct Specifier {
   /// The name importer used
   pub used: JsWord,
   pub mark: Mark,
+  pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -382,10 +372,20 @@ impl RelationInfo {
   }
 }
 
+// String literal names, such as `export { foo as "foo-bar" }`, can't be rendered as bindings yet.
 #[inline]
-fn get_sym_from_module_export(module_export_name: &ModuleExportName) -> JsWord {
+fn get_sym_from_module_export(module_export_name: &ModuleExportName) -> Result<JsWord, BuildError> {
   match module_export_name {
-    ModuleExportName::Ident(i) => i.sym.clone(),
-    _ => panic!(""),
+    ModuleExportName::Ident(i) => Ok(i.sym.clone()),
+    ModuleExportName::Str(s) => Err(
+      BuildError::new(
+        ErrorCode::UnsupportedSyntax,
+        format!(
+          "String literal export names are not supported, such as \"{}\"",
+          s.value
+        ),
+      )
+      .with_span(s.span),
+    ),
   }
 }
@@ -14,6 +14,10 @@ pub struct Statement {
   pub reads: HashSet<Mark>,
   pub writes: HashSet<Mark>,
   pub side_effect: Option<SideEffect>,
+  // Functions declared in the bundle which are called by the statement.
+  pub callees: HashSet<Mark>,
+  // `foo.bar` => `(foo, bar)`. `foo` is not in `reads` unless it's used in other ways.
+  pub member_reads: HashSet<(Mark, JsWord)>,
 }
 
 impl Statement {
@@ -25,6 +29,8 @@ impl Statement {
       reads: Default::default(),
       writes: Default::default(),
       side_effect: Default::default(),
+      callees: Default::default(),
+      member_reads: Default::default(),
     }
   }
 
@@ -42,6 +48,8 @@ impl std::fmt::Debug for Statement {
       .field("reads", &self.reads)
       .field("writes", &self.writes)
       .field("side_effect", &self.side_effect)
+      .field("callees", &self.callees)
+      .field("member_reads", &self.member_reads)
       .finish()
   }
 }
@@ -1,6 +1,45 @@
 use ena::unify::{InPlaceUnificationTable, UnifyKey};
-use once_cell::sync::Lazy;
-use swc_common::{Globals, Mark, GLOBALS};
+use swc_common::Mark;
+
+// Marks of a single module. They're numbered from `1` while the module is scanned, so scanning
+// threads never share a counter, and moved after the marks of modules executed earlier once all
+// modules are scanned. See `Module::renumber_marks`.
+#[derive(Debug)]
+pub struct LocalMarks {
+  next: u32,
+}
+
+impl Default for LocalMarks {
+  fn default() -> Self {
+    Self {
+      // Mark(0) is a special mark in SWC. we need to drop it.
+      next: 1,
+    }
+  }
+}
+
+impl LocalMarks {
+  pub fn new_mark(&mut self) -> Mark {
+    let mark = Mark::from_u32(self.next);
+    self.next += 1;
+    mark
+  }
+
+  // Number of allocated marks.
+  pub fn count(&self) -> u32 {
+    self.next - 1
+  }
+}
+
+// Moves a mark of `LocalMarks` after the marks of other modules. Mark(0) isn't allocated by
+// `LocalMarks`, so it's kept.
+pub fn renumber_mark(mark: Mark, offset: u32) -> Mark {
+  if mark.as_u32() == 0 {
+    mark
+  } else {
+    Mark::from_u32(mark.as_u32() + offset)
+  }
+}
 
 #[derive(Debug)]
 pub struct SymbolBox {
@@ -8,8 +47,6 @@ pub struct SymbolBox {
   // globals: Globals,
 }
 
-pub(crate) static SYMBOL_GLOBALS: Lazy<Globals> = Lazy::new(Globals::new);
-
 impl Default for SymbolBox {
   fn default() -> Self {
     Self::new()
@@ -19,21 +56,19 @@ impl Default for SymbolBox {
 impl SymbolBox {
   pub fn new() -> Self {
     let mut mark_uf: InPlaceUnificationTable<MarkIndex> = Default::default();
-    GLOBALS.set(&SYMBOL_GLOBALS, || {
-      // Mark(0) is a special mark in SWC. we need to drop it.
-      mark_uf.new_key(());
-    });
+    // Mark(0) is a special mark in SWC. we need to drop it.
+    mark_uf.new_key(());
     Self {
       // globals,
       mark_uf,
     }
   }
 
-  pub fn new_mark(&mut self) -> Mark {
-    GLOBALS.set(&SYMBOL_GLOBALS, || {
-      // Mark(0) is a special mark in SWC. we need to drop it.
-      self.mark_uf.new_key(()).as_mark()
-    })
+  // Make every mark below `allocated` a key of the union-find.
+  pub fn reserve(&mut self, allocated: u32) {
+    while (self.mark_uf.len() as u32) < allocated {
+      self.mark_uf.new_key(());
+    }
   }
 
   pub fn union(&mut self, a: Mark, b: Mark) {
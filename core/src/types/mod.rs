@@ -4,12 +4,16 @@ mod normalized_input_options;
 pub use normalized_input_options::*;
 mod normalized_output_options;
 pub use normalized_output_options::*;
+mod normalized_treeshaking_options;
+pub use normalized_treeshaking_options::*;
 use smol_str::SmolStr;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct ResolvedId {
   pub id: SmolStr,
   pub external: bool,
+  // Overrides the `sideEffects` field of `package.json` and the `module_side_effects` option.
+  pub module_side_effects: Option<bool>,
 }
 
 impl ResolvedId {
@@ -17,9 +21,32 @@ impl ResolvedId {
     Self {
       id,
       external,
-      // module_side_effects: false,
+      module_side_effects: None,
     }
   }
+
+  pub fn with_module_side_effects(mut self, module_side_effects: bool) -> Self {
+    self.module_side_effects = Some(module_side_effects);
+    self
+  }
 }
 
 pub type ResolveIdResult = Option<ResolvedId>;
+
+#[derive(Debug, Clone, Default)]
+pub struct LoadedModule {
+  pub code: String,
+  // Overrides the one returned by `resolve_id`.
+  pub module_side_effects: Option<bool>,
+}
+
+impl From<String> for LoadedModule {
+  fn from(code: String) -> Self {
+    Self {
+      code,
+      module_side_effects: None,
+    }
+  }
+}
+
+pub type LoadResult = Option<LoadedModule>;
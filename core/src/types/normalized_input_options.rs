@@ -1,8 +1,76 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::Value;
+
+use super::NormalizedTreeshakingOptions;
+use crate::{
+  plugin_driver::Plugin,
+  utils::{import_map::ImportMap, is_relative, tsconfig::TsconfigPaths},
+  warning::WarningHandler,
+};
+
 // (source: &str, importer: Option<&str>, is_resolved: bool)
 pub type IsExternal = Box<dyn Fn(&str, Option<&str>, bool) -> bool>;
 
 // type ModuleContext = Box<dyn Fn(&str) -> &str>;
 
+/// Whether absolute external ids should be rendered relative to the output chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MakeAbsoluteExternalsRelative {
+  Bool(bool),
+  // Only renormalize ids whose import source was relative, such as `import './vendor/foo'`.
+  IfRelativeSource,
+}
+
+impl Default for MakeAbsoluteExternalsRelative {
+  fn default() -> Self {
+    Self::Bool(true)
+  }
+}
+
+impl MakeAbsoluteExternalsRelative {
+  #[inline]
+  pub fn should_renormalize(&self, id: &str, source: &str) -> bool {
+    nodejs_path::is_absolute(id)
+      && match self {
+        Self::Bool(value) => *value,
+        Self::IfRelativeSource => is_relative(source),
+      }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum AliasFind {
+  // `find` only matches `find` itself.
+  Exact(String),
+  // `find` matches `find` itself and `find/*`, just like `@rollup/plugin-alias`.
+  Prefix(String),
+  // `replacement` could refer to capture groups, such as `$1`.
+  Regex(Regex),
+}
+
+#[derive(Debug, Clone)]
+pub struct Alias {
+  pub find: AliasFind,
+  pub replacement: String,
+}
+
+impl Alias {
+  pub fn resolve(&self, source: &str) -> Option<String> {
+    match &self.find {
+      AliasFind::Exact(find) => (find == source).then(|| self.replacement.clone()),
+      AliasFind::Prefix(find) => {
+        let rest = source.strip_prefix(find.as_str())?;
+        (rest.is_empty() || rest.starts_with('/')).then(|| self.replacement.clone() + rest)
+      }
+      AliasFind::Regex(find) => find
+        .is_match(source)
+        .then(|| find.replace(source, self.replacement.as_str()).to_string()),
+    }
+  }
+}
+
 // type EntryAlias = String;
 
 #[derive(Default)]
@@ -22,22 +90,34 @@ pub struct NormalizedInputOptions {
 
   // --- Options that Rolldown might need to supported
   // cache: false | undefined | RollupCache;
-  // makeAbsoluteExternalsRelative: boolean | 'ifRelativeSource';
-  // maxParallelFileReads: number;
-  // onwarn: WarningHandler;
   // perf: boolean;
   // preserveEntrySignatures: PreserveEntrySignaturesOption;
   // shimMissingExports: boolean;
   // pub module_context: ModuleContext,
+  pub make_absolute_externals_relative: MakeAbsoluteExternalsRelative,
+  // Applied in order after `import_map` and before any other resolution. The first matched one wins.
+  pub alias: Vec<Alias>,
+  // Opt-in. Bare imports are resolved via `baseUrl` and `paths` of the tsconfig before plugins.
+  pub tsconfig: Option<TsconfigPaths>,
+  // Opt-in. Consulted before any other resolution with the specifiers as they are written, including
+  // entries. Mapped URLs are kept as externals.
+  pub import_map: Option<ImportMap>,
+  // Warnings are logged via `log::warn!` if it's not provided.
+  pub on_warn: Option<WarningHandler>,
+  // Number of threads reading and scanning modules. Defaults to the number of CPUs.
+  pub max_parallel_file_reads: Option<usize>,
+  // Expressions replaced with JSON values before scanning, such as `process.env.NODE_ENV`,
+  // `__DEV__` and `import.meta.env.MODE`.
+  pub define: HashMap<String, Value>,
 
   // --- Options that Rolldown must need to be supported
-  pub treeshake: bool,
-  // pub treeshake: bool | NormalizedTreeshakingOptions;
-  // pub plugins: Vec<Box<dyn Plugin>>,
+  // Tree-shaking is disabled with `None`.
+  pub treeshake: Option<NormalizedTreeshakingOptions>,
+  pub plugins: Vec<Box<dyn Plugin + Send + Sync>>,
   // By default, the context of a module – i.e., the value of this at the top level – is undefined. In rare cases you might need to change this to something else, like 'window'.
   // pub context: Option<String>,
   // pub external: IsExternal,
   // (alias: Option<String>, path: String)
   pub input: Vec<String>,
-  // pub preserve_symlinks: bool,
+  pub preserve_symlinks: bool,
 }
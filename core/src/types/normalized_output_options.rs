@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum InternalModuleFormat {
   ES,
@@ -6,6 +8,22 @@ pub enum InternalModuleFormat {
   UMD,
 }
 
+// Rewrites the specifier written for an external import, such as `lodash` => `https://cdn.skypack.dev/lodash`.
+pub enum OptionsPaths {
+  Map(HashMap<String, String>),
+  Fn(Box<dyn Fn(&str) -> String + Send + Sync>),
+}
+
+impl OptionsPaths {
+  #[inline]
+  pub fn get(&self, id: &str) -> Option<String> {
+    match self {
+      Self::Map(paths) => paths.get(id).cloned(),
+      Self::Fn(paths) => Some(paths(id)),
+    }
+  }
+}
+
 pub struct NormalizedOutputOptions {
   // --- Options Rolldown doesn't need to be supported
   // /** @deprecated Use the "renderDynamicImport" plugin hook instead. */
@@ -39,7 +57,7 @@ pub struct NormalizedOutputOptions {
   // namespaceToStringTag: boolean;
   // noConflict: boolean;
   // outro: () => string | Promise<string>;
-  // paths: OptionsPaths;
+  pub paths: Option<OptionsPaths>,
   // plugins: OutputPlugin[];
   // preferConst: boolean;
   // preserveModules: boolean;
@@ -63,6 +81,7 @@ impl Default for NormalizedOutputOptions {
       file: Default::default(),
       dir: Default::default(),
       minify: Default::default(),
+      paths: Default::default(),
       entry_file_names: "[name].js".to_string(),
     }
   }
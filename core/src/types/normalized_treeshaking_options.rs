@@ -0,0 +1,109 @@
+// Whether reading a property of an object might have side effects, such as triggering a getter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyReadSideEffects {
+  // With `true`, properties of literals and known globals like `Math` are still pure to read.
+  Bool(bool),
+  // Every property read is considered to have side effects.
+  Always,
+}
+
+impl Default for PropertyReadSideEffects {
+  fn default() -> Self {
+    Self::Bool(true)
+  }
+}
+
+// (id: &str, external: bool)
+pub type HasModuleSideEffects = Box<dyn Fn(&str, bool) -> bool + Send + Sync>;
+
+// Whether a module should be kept for its side effects even if none of its exports are used.
+// Plugins and the `sideEffects` field of `package.json` take precedence.
+pub enum ModuleSideEffects {
+  Bool(bool),
+  // Only the listed modules have side effects.
+  List(Vec<String>),
+  Predicate(HasModuleSideEffects),
+}
+
+impl Default for ModuleSideEffects {
+  fn default() -> Self {
+    Self::Bool(true)
+  }
+}
+
+impl ModuleSideEffects {
+  pub fn resolve(&self, id: &str, external: bool) -> bool {
+    match self {
+      Self::Bool(value) => *value,
+      Self::List(ids) => ids.iter().any(|listed| listed == id),
+      Self::Predicate(predicate) => predicate(id, external),
+    }
+  }
+}
+
+// Align to https://rollupjs.org/guide/en/#treeshake
+pub struct NormalizedTreeshakingOptions {
+  // Respect `/*#__PURE__*/` and `/*#__NO_SIDE_EFFECTS__*/`.
+  pub annotations: bool,
+  // Assume `var` bindings might be read before their declarations run, so that their initial values
+  // are never inlined.
+  pub correct_var_value_before_declaration: bool,
+  pub module_side_effects: ModuleSideEffects,
+  pub property_read_side_effects: PropertyReadSideEffects,
+  // Keep statements in `try` blocks as they are, since they might be there to catch errors.
+  pub try_catch_deoptimization: bool,
+  // Whether reading an unknown global variable might have side effects, such as throwing a
+  // `ReferenceError`.
+  pub unknown_global_side_effects: bool,
+}
+
+// Same as `treeshake: true` of Rollup.
+impl Default for NormalizedTreeshakingOptions {
+  fn default() -> Self {
+    Self {
+      annotations: true,
+      correct_var_value_before_declaration: false,
+      module_side_effects: ModuleSideEffects::Bool(true),
+      property_read_side_effects: PropertyReadSideEffects::Bool(true),
+      try_catch_deoptimization: true,
+      unknown_global_side_effects: true,
+    }
+  }
+}
+
+impl NormalizedTreeshakingOptions {
+  // Remove as much code as possible, which might break code relying on side effects of modules,
+  // getters or globals.
+  pub fn smallest() -> Self {
+    Self {
+      annotations: true,
+      correct_var_value_before_declaration: false,
+      module_side_effects: ModuleSideEffects::Bool(false),
+      property_read_side_effects: PropertyReadSideEffects::Bool(false),
+      try_catch_deoptimization: false,
+      unknown_global_side_effects: false,
+    }
+  }
+
+  // Keep everything which might have side effects.
+  pub fn safest() -> Self {
+    Self {
+      annotations: true,
+      correct_var_value_before_declaration: true,
+      module_side_effects: ModuleSideEffects::Bool(true),
+      property_read_side_effects: PropertyReadSideEffects::Bool(true),
+      try_catch_deoptimization: true,
+      unknown_global_side_effects: true,
+    }
+  }
+
+  // Same as `safest`, but assume that unknown globals exist and `var` bindings are declared before
+  // they are read.
+  pub fn recommended() -> Self {
+    Self {
+      correct_var_value_before_declaration: false,
+      unknown_global_side_effects: false,
+      ..Self::safest()
+    }
+  }
+}
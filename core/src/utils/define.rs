@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use swc_common::{util::take::Take, Span, Spanned, SyntaxContext};
+use swc_ecma_ast::{
+  ArrayLit, AssignExpr, Bool, Expr, ExprOrSpread, KeyValueProp, Lit, MemberExpr, MemberProp,
+  MetaPropExpr, Null, Number, ObjectLit, ParenExpr, Pat, PatOrExpr, Prop, PropName, PropOrSpread,
+  Str, UpdateExpr,
+};
+use swc_ecma_visit::{noop_visit_mut_type, VisitMut, VisitMutWith};
+
+use crate::scanner::helper::static_member_prop;
+
+// `process.env.NODE_ENV` => `Some("process.env.NODE_ENV")`. Identifiers declared in the module, such
+// as the parameter of `function read(process) {}`, have been marked by the scanner and shadow globals.
+fn expr_path(expr: &Expr) -> Option<String> {
+  match expr {
+    Expr::Ident(ident) if ident.span.ctxt == SyntaxContext::empty() => Some(ident.sym.to_string()),
+    Expr::MetaProp(MetaPropExpr { meta, prop }) => Some(format!("{}.{}", meta.sym, prop.sym)),
+    Expr::Member(MemberExpr { obj, prop, .. }) => {
+      Some(format!("{}.{}", expr_path(obj)?, static_member_prop(prop)?))
+    }
+    _ => None,
+  }
+}
+
+fn json_to_expr(value: &Value, span: Span) -> Expr {
+  match value {
+    Value::Null => Expr::Lit(Lit::Null(Null { span })),
+    Value::Bool(value) => Expr::Lit(Lit::Bool(Bool {
+      span,
+      value: *value,
+    })),
+    Value::Number(value) => Expr::Lit(Lit::Num(Number {
+      span,
+      value: value.as_f64().unwrap_or(f64::NAN),
+    })),
+    Value::String(value) => Expr::Lit(Lit::Str(Str {
+      span,
+      value: value.as_str().into(),
+      ..Str::dummy()
+    })),
+    Value::Array(elems) => Expr::Array(ArrayLit {
+      span,
+      elems: elems
+        .iter()
+        .map(|elem| {
+          Some(ExprOrSpread {
+            spread: None,
+            expr: Box::new(json_to_expr(elem, span)),
+          })
+        })
+        .collect(),
+    }),
+    Value::Object(props) => Expr::Object(ObjectLit {
+      span,
+      props: props
+        .iter()
+        .map(|(key, value)| {
+          PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+            key: PropName::Str(Str {
+              span,
+              value: key.as_str().into(),
+              ..Str::dummy()
+            }),
+            value: Box::new(json_to_expr(value, span)),
+          })))
+        })
+        .collect(),
+    }),
+  }
+}
+
+// Replaces expressions listed in the `define` option with their values. The scanner runs it on each
+// statement after resolving its identifiers, so that only globals are replaced, and the replaced
+// values take part in side effect analysis and constant folding.
+pub struct DefineReplacer<'a> {
+  define: &'a HashMap<String, Value>,
+}
+
+impl<'a> DefineReplacer<'a> {
+  pub fn new(define: &'a HashMap<String, Value>) -> Self {
+    Self { define }
+  }
+
+  // Visits the computed parts of an assignment target without replacing the target itself, such as
+  // `process.env.KEY` in `target[process.env.KEY] = 1`.
+  fn visit_mut_target(&mut self, target: &mut Expr) {
+    match target {
+      Expr::Member(MemberExpr { obj, prop, .. }) => {
+        self.visit_mut_target(obj);
+        if let MemberProp::Computed(computed) = prop {
+          computed.expr.visit_mut_with(self);
+        }
+      }
+      Expr::Paren(ParenExpr { expr, .. }) => self.visit_mut_target(expr),
+      Expr::Ident(_) => {}
+      _ => target.visit_mut_with(self),
+    }
+  }
+}
+
+impl<'a> VisitMut for DefineReplacer<'a> {
+  noop_visit_mut_type!();
+
+  fn visit_mut_expr(&mut self, expr: &mut Expr) {
+    let replaced = expr_path(expr).and_then(|path| self.define.get(&path));
+    match replaced {
+      Some(value) => *expr = json_to_expr(value, expr.span()),
+      None => expr.visit_mut_children_with(self),
+    }
+  }
+
+  // Assignment targets are kept, such as `process.env.NODE_ENV = 'test'`.
+  fn visit_mut_pat(&mut self, pat: &mut Pat) {
+    match pat {
+      Pat::Expr(expr) => self.visit_mut_target(expr),
+      _ => pat.visit_mut_children_with(self),
+    }
+  }
+
+  fn visit_mut_assign_expr(&mut self, assign_expr: &mut AssignExpr) {
+    match &mut assign_expr.left {
+      PatOrExpr::Expr(expr) => self.visit_mut_target(expr),
+      PatOrExpr::Pat(pat) => pat.visit_mut_with(self),
+    }
+    assign_expr.right.visit_mut_with(self);
+  }
+
+  fn visit_mut_update_expr(&mut self, update_expr: &mut UpdateExpr) {
+    self.visit_mut_target(&mut update_expr.arg);
+  }
+}
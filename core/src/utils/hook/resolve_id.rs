@@ -1,29 +1,106 @@
 use std::{ffi::OsString, path::Path};
 
 use crate::{
-  ext::PathExt, plugin_driver::PluginDriver, types::ResolvedId, utils::is_external_module,
+  error::{BuildError, ErrorCode},
+  ext::PathExt,
+  plugin_driver::PluginDriver,
+  types::{NormalizedInputOptions, ResolvedId},
+  utils::{import_map::ImportMapTarget, is_external_module, path::relative_id},
 };
 
 #[inline]
 pub fn resolve_id(
   source: &str,
   importer: Option<&str>,
-  preserve_symlinks: bool,
-  // plugin_driver: &PluginDriver,
-) -> ResolvedId {
-  if importer.is_some() && is_external_module(source) {
-    ResolvedId::new(source.to_string().into(), true)
+  input_options: &NormalizedInputOptions,
+  plugin_driver: &PluginDriver,
+) -> Result<ResolvedId, BuildError> {
+  // The import map applies to specifiers as they are written, so aliases never change what it
+  // matches.
+  let mapped = input_options
+    .import_map
+    .as_ref()
+    .and_then(|import_map| match importer {
+      Some(importer) => import_map.resolve(source, importer),
+      None => import_map.resolve_entry(source),
+    });
+  match (mapped, importer) {
+    (Some(ImportMapTarget::Path(id)), _) => return Ok(ResolvedId::new(id.into(), false)),
+    (Some(ImportMapTarget::Url(url)), Some(_)) => return Ok(ResolvedId::new(url.into(), true)),
+    (Some(ImportMapTarget::Blocked), Some(importer)) => {
+      let mut error = unresolved_import_error(source, importer);
+      error
+        .message
+        .push_str(", which is blocked by the import map");
+      return Err(error);
+    }
+    // Entries can't be external.
+    (Some(_), None) => return Err(unresolved_entry_error(source)),
+    (None, _) => {}
+  }
+
+  // Entries are never aliased.
+  let aliased = importer.and_then(|_| {
+    input_options
+      .alias
+      .iter()
+      .find_map(|alias| alias.resolve(source))
+  });
+  let source = aliased.as_deref().unwrap_or(source);
+
+  if let Some(id) = importer
+    .and(input_options.tsconfig.as_ref())
+    .and_then(|tsconfig| tsconfig.resolve(source))
+  {
+    Ok(ResolvedId::new(id.into(), false))
+  } else if let Some(resolved_id) = resolve_id_via_plugins(source, importer, plugin_driver) {
+    Ok(resolved_id)
+  } else if importer.is_some() && is_external_module(source) {
+    Ok(ResolvedId::new(source.to_string().into(), true))
   } else {
-    let id = if let Some(importer) = importer {
+    let file = if let Some(importer) = importer {
       nodejs_path::resolve!(&nodejs_path::dirname(importer), source)
     } else {
       nodejs_path::resolve!(source)
     };
-    let id = fast_add_js_extension_if_necessary(id, preserve_symlinks);
-    ResolvedId::new(id.into(), false)
+    let id = fast_add_js_extension_if_necessary(file.clone(), input_options.preserve_symlinks);
+    // Such as directories or files with other extensions, which aliases usually point to.
+    let id = if Path::new(&id).is_file() {
+      Some(id)
+    } else {
+      resolve_file(&file)
+    };
+    match id {
+      Some(id) => Ok(ResolvedId::new(id.into(), false)),
+      None => Err(match importer {
+        Some(importer) => unresolved_import_error(source, importer),
+        None => unresolved_entry_error(source),
+      }),
+    }
   }
 }
 
+#[inline]
+fn unresolved_import_error(source: &str, importer: &str) -> BuildError {
+  BuildError::new(
+    ErrorCode::UnresolvedImport,
+    format!(
+      "Could not resolve \"{}\" from \"{}\"",
+      source,
+      relative_id(importer.to_string())
+    ),
+  )
+  .with_id(importer)
+}
+
+#[inline]
+fn unresolved_entry_error(source: &str) -> BuildError {
+  BuildError::new(
+    ErrorCode::UnresolvedEntry,
+    format!("Could not resolve entry module \"{}\"", source),
+  )
+}
+
 #[inline]
 pub fn resolve_id_via_plugins(
   source: &str,
@@ -72,3 +149,25 @@ pub fn find_file(file: &Path, preserve_symlinks: bool) -> Option<String> {
     None
   }
 }
+
+const EXTENSIONS: [&str; 5] = [".ts", ".tsx", ".mjs", ".js", ".jsx"];
+
+// Find the file `path` refers to, by trying it as it is, with extensions and as a directory.
+pub fn resolve_file(path: &str) -> Option<String> {
+  resolve_file_with(path, |file| Path::new(file).is_file())
+}
+
+pub fn resolve_file_with(path: &str, mut is_file: impl FnMut(&str) -> bool) -> Option<String> {
+  if is_file(path) {
+    return Some(path.to_string());
+  }
+  EXTENSIONS
+    .iter()
+    .map(|ext| format!("{}{}", path, ext))
+    .chain(
+      EXTENSIONS
+        .iter()
+        .map(|ext| nodejs_path::resolve!(path, &format!("index{}", ext))),
+    )
+    .find(|file| is_file(file))
+}
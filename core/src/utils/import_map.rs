@@ -0,0 +1,155 @@
+use serde_json::{Map, Value};
+
+use crate::utils::{is_relative, resolve_file};
+
+// (specifier key, address). `None` address blocks the specifier.
+type SpecifierMap = Vec<(String, Option<String>)>;
+
+// A WHATWG import map. See https://github.com/WICG/import-maps.
+#[derive(Debug, Default, Clone)]
+pub struct ImportMap {
+  // Relative and root-relative addresses, such as `./src/app.js` and `/src/app.js`, are resolved against this directory.
+  pub base_dir: String,
+  imports: SpecifierMap,
+  // (scope prefix, specifier map). Sorted from the most specific scope.
+  scopes: Vec<(String, SpecifierMap)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportMapTarget {
+  Path(String),
+  // Such as `https://cdn.skypack.dev/react`. Kept as external.
+  Url(String),
+  Blocked,
+}
+
+impl ImportMap {
+  pub fn from_file(path: &str) -> anyhow::Result<Self> {
+    let path = nodejs_path::resolve!(path);
+    let source = std::fs::read_to_string(&path)?;
+    Self::from_json(&nodejs_path::dirname(&path), &source)
+  }
+
+  pub fn from_json(base_dir: &str, source: &str) -> anyhow::Result<Self> {
+    let json: Value = serde_json::from_str(source)?;
+    let mut import_map = Self {
+      base_dir: base_dir.to_string(),
+      ..Default::default()
+    };
+
+    if let Some(imports) = json.get("imports").and_then(Value::as_object) {
+      import_map.imports = import_map.normalize_specifier_map(imports);
+    }
+    if let Some(scopes) = json.get("scopes").and_then(Value::as_object) {
+      import_map.scopes = scopes
+        .iter()
+        .filter_map(|(scope, imports)| {
+          let imports = imports.as_object()?;
+          Some((
+            import_map.normalize_key(scope),
+            import_map.normalize_specifier_map(imports),
+          ))
+        })
+        .collect();
+      import_map
+        .scopes
+        .sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    }
+    Ok(import_map)
+  }
+
+  fn normalize_specifier_map(&self, imports: &Map<String, Value>) -> SpecifierMap {
+    let mut specifier_map = imports
+      .iter()
+      .map(|(key, address)| {
+        (
+          self.normalize_key(key),
+          address.as_str().map(|address| address.to_string()),
+        )
+      })
+      .collect::<Vec<_>>();
+    // The longest key wins when several prefixes match.
+    specifier_map.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    specifier_map
+  }
+
+  // Turns path-like keys into absolute paths so they can be compared with resolved specifiers.
+  fn normalize_key(&self, key: &str) -> String {
+    match self.resolve_path(key) {
+      Some(path) if key.ends_with('/') => path + "/",
+      Some(path) => path,
+      None => key.to_string(),
+    }
+  }
+
+  fn resolve_path(&self, path: &str) -> Option<String> {
+    if is_relative(path) {
+      Some(nodejs_path::resolve!(&self.base_dir, path))
+    } else if path.starts_with('/') {
+      Some(nodejs_path::resolve!(&self.base_dir, &format!(".{}", path)))
+    } else {
+      None
+    }
+  }
+
+  pub fn resolve(&self, source: &str, importer: &str) -> Option<ImportMapTarget> {
+    let specifier = normalize_specifier(source, &nodejs_path::dirname(importer));
+    self
+      .scopes
+      .iter()
+      .filter(|(scope, _)| {
+        importer == scope || (scope.ends_with('/') && importer.starts_with(scope.as_str()))
+      })
+      .map(|(_, imports)| imports)
+      .chain(std::iter::once(&self.imports))
+      .find_map(|imports| self.resolve_imports_match(&specifier, imports))
+  }
+
+  // Entries have no importer, so relative entries are resolved against the working directory and
+  // scopes don't apply.
+  pub fn resolve_entry(&self, source: &str) -> Option<ImportMapTarget> {
+    let specifier = normalize_specifier(source, &nodejs_path::resolve!("."));
+    self.resolve_imports_match(&specifier, &self.imports)
+  }
+
+  fn resolve_imports_match(
+    &self,
+    specifier: &str,
+    imports: &SpecifierMap,
+  ) -> Option<ImportMapTarget> {
+    imports.iter().find_map(|(key, address)| {
+      let after_prefix = if key == specifier {
+        ""
+      } else if key.ends_with('/') && specifier.starts_with(key.as_str()) {
+        &specifier[key.len()..]
+      } else {
+        return None;
+      };
+      match address {
+        // A prefix key must map to a prefix address.
+        Some(address) if after_prefix.is_empty() || address.ends_with('/') => {
+          Some(self.to_target(&format!("{}{}", address, after_prefix)))
+        }
+        _ => Some(ImportMapTarget::Blocked),
+      }
+    })
+  }
+
+  fn to_target(&self, address: &str) -> ImportMapTarget {
+    match self.resolve_path(address) {
+      Some(path) => ImportMapTarget::Path(resolve_file(&path).unwrap_or(path)),
+      None if address.contains(':') => ImportMapTarget::Url(address.to_string()),
+      // Bare addresses are invalid.
+      None => ImportMapTarget::Blocked,
+    }
+  }
+}
+
+// Relative specifiers are matched by the path they point to.
+fn normalize_specifier(source: &str, dir: &str) -> String {
+  if is_relative(source) || nodejs_path::is_absolute(source) {
+    nodejs_path::resolve!(dir, source)
+  } else {
+    source.to_string()
+  }
+}
@@ -1,16 +1,17 @@
 pub mod ast_sugar;
+pub mod define;
+pub mod import_map;
 mod lcp;
 pub mod name_helpers;
+pub mod package_json;
 pub mod side_effect;
+pub mod tsconfig;
 pub use lcp::*;
 use std::path::Path;
 
 use swc_ecma_ast::{EsVersion, ModuleDecl, ModuleItem};
 
-use swc_common::{
-  errors::{ColorConfig, Handler},
-  FileName,
-};
+use swc_common::{comments::Comments, FileName, Spanned};
 use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax};
 use swc_ecma_parser::{EsConfig, TsConfig};
 
@@ -19,7 +20,10 @@ mod statement;
 pub use hook::*;
 pub use statement::*;
 
-use crate::compiler::SOURCE_MAP;
+use crate::{
+  compiler::SOURCE_MAP,
+  error::{BuildError, ErrorCode},
+};
 
 pub mod path {
   pub fn relative_id(id: String) -> String {
@@ -29,6 +33,31 @@ pub mod path {
       id
     }
   }
+
+  // The deepest directory containing all the given paths.
+  pub fn common_dir<'a>(paths: impl IntoIterator<Item = &'a str>) -> String {
+    let dirs = paths
+      .into_iter()
+      .map(nodejs_path::dirname)
+      .collect::<Vec<_>>();
+    let mut common = match dirs.first() {
+      Some(dir) => dir.split('/').collect::<Vec<_>>(),
+      None => return nodejs_path::resolve!("."),
+    };
+    dirs.iter().skip(1).for_each(|dir| {
+      let len = common
+        .iter()
+        .zip(dir.split('/'))
+        .take_while(|(a, b)| *a == b)
+        .count();
+      common.truncate(len);
+    });
+    if common.len() <= 1 {
+      "/".to_string()
+    } else {
+      common.join("/")
+    }
+  }
 }
 
 #[inline]
@@ -36,6 +65,11 @@ pub fn is_external_module(source: &str) -> bool {
   source.starts_with("node:") || (!nodejs_path::is_absolute(source) && !source.starts_with('.'))
 }
 
+#[inline]
+pub fn is_relative(source: &str) -> bool {
+  source.starts_with("./") || source.starts_with("../")
+}
+
 #[inline]
 pub fn is_decl_or_stmt(node: &ModuleItem) -> bool {
   matches!(
@@ -48,8 +82,13 @@ pub fn is_decl_or_stmt(node: &ModuleItem) -> bool {
   )
 }
 
-pub fn parse_file(source_code: String, filename: &str) -> swc_ecma_ast::Module {
-  let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(SOURCE_MAP.clone()));
+// All syntax errors of the file are reported, including the recoverable ones. Comments are collected
+// into `comments` if it's given.
+pub fn parse_file(
+  source_code: String,
+  filename: &str,
+  comments: Option<&dyn Comments>,
+) -> Result<swc_ecma_ast::Module, Vec<BuildError>> {
   let p = Path::new(filename);
   let fm = SOURCE_MAP.new_source_file(FileName::Custom(filename.to_owned()), source_code);
   let ext = p.extension().and_then(|ext| ext.to_str()).unwrap_or("js");
@@ -77,11 +116,26 @@ pub fn parse_file(source_code: String, filename: &str) -> swc_ecma_ast::Module {
     syntax,
     EsVersion::latest(),
     StringInput::from(fm.as_ref()),
-    None,
+    comments,
   );
   let mut parser = Parser::new_from(lexer);
-  parser.take_errors().into_iter().for_each(|e| {
-    e.into_diagnostic(&handler).emit();
-  });
-  parser.parse_module().unwrap()
+  let to_build_error = |error: swc_ecma_parser::error::Error| {
+    BuildError::new(ErrorCode::ParseError, error.kind().msg())
+      .with_id(filename)
+      .with_span(error.span())
+  };
+  let result = parser.parse_module();
+  let mut errors = parser
+    .take_errors()
+    .into_iter()
+    .map(to_build_error)
+    .collect::<Vec<_>>();
+  match result {
+    Ok(module) if errors.is_empty() => Ok(module),
+    Ok(_) => Err(errors),
+    Err(error) => {
+      errors.push(to_build_error(error));
+      Err(errors)
+    }
+  }
 }
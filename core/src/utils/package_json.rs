@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone)]
+pub struct PackageJson {
+  // The directory containing the `package.json`.
+  pub dir: String,
+  pub raw: Value,
+}
+
+impl PackageJson {
+  // Reads `package.json` of `dir` with `read_to_string`, which might read from zip archives.
+  pub fn read(dir: &str, read_to_string: impl FnOnce(&str) -> Option<String>) -> Option<Self> {
+    let source = read_to_string(&nodejs_path::resolve!(dir, "package.json"))?;
+    let raw = serde_json::from_str(&source).ok()?;
+    Some(Self {
+      dir: dir.to_string(),
+      raw,
+    })
+  }
+
+  #[inline]
+  pub fn get_str(&self, field: &str) -> Option<&str> {
+    self.raw.get(field).and_then(Value::as_str)
+  }
+
+  // The object form of the `browser` field, such as `{ "./lib/node.js": "./lib/browser.js", "fs": false }`.
+  #[inline]
+  pub fn browser_map(&self) -> Option<&Map<String, Value>> {
+    self.raw.get("browser").and_then(Value::as_object)
+  }
+
+  // The `sideEffects` field, which is either a boolean or globs of files having side effects.
+  // Globs without `/` match files in any directory, just like webpack.
+  pub fn has_side_effects(&self, id: &str) -> Option<bool> {
+    match self.raw.get("sideEffects")? {
+      Value::Bool(value) => Some(*value),
+      Value::Array(globs) => {
+        let relative = nodejs_path::relative(&self.dir, id);
+        let has_side_effects = globs.iter().filter_map(Value::as_str).any(|glob| {
+          let glob = glob.strip_prefix("./").unwrap_or(glob);
+          let glob = if glob.contains('/') {
+            glob.to_string()
+          } else {
+            format!("**/{}", glob)
+          };
+          glob_to_regex(&glob).map_or(false, |regex| regex.is_match(&relative))
+        });
+        Some(has_side_effects)
+      }
+      _ => None,
+    }
+  }
+}
+
+// `src/**/*.{css,scss}` => `^src/(?:.*/)?[^/]*\.(?:css|scss)$`
+fn glob_to_regex(glob: &str) -> Option<Regex> {
+  let mut pattern = String::from("^");
+  let mut brace_depth = 0;
+  let mut chars = glob.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '*' if chars.peek() == Some(&'*') => {
+        chars.next();
+        // `**/` also matches no directory.
+        if chars.next_if_eq(&'/').is_some() {
+          pattern.push_str("(?:.*/)?");
+        } else {
+          pattern.push_str(".*");
+        }
+      }
+      '*' => pattern.push_str("[^/]*"),
+      '?' => pattern.push_str("[^/]"),
+      '{' => {
+        brace_depth += 1;
+        pattern.push_str("(?:");
+      }
+      '}' if brace_depth > 0 => {
+        brace_depth -= 1;
+        pattern.push(')');
+      }
+      ',' if brace_depth > 0 => pattern.push('|'),
+      c => pattern.push_str(&regex::escape(&c.to_string())),
+    }
+  }
+  pattern.push('$');
+  Regex::new(&pattern).ok()
+}
+
+// The nearest `package.json` of each directory, which is shared by workers and the node resolver.
+#[derive(Debug, Default)]
+pub struct PackageJsonCache {
+  by_dir: DashMap<String, Option<Arc<PackageJson>>>,
+}
+
+impl PackageJsonCache {
+  pub fn find_up(
+    &self,
+    dir: &str,
+    read_to_string: &mut impl FnMut(&str) -> Option<String>,
+  ) -> Option<Arc<PackageJson>> {
+    if let Some(package) = self.by_dir.get(dir) {
+      return package.clone();
+    }
+    let package = PackageJson::read(dir, &mut *read_to_string)
+      .map(Arc::new)
+      .or_else(|| {
+        let parent = nodejs_path::dirname(dir);
+        if parent == dir {
+          None
+        } else {
+          self.find_up(&parent, read_to_string)
+        }
+      });
+    self.by_dir.insert(dir.to_string(), package.clone());
+    package
+  }
+}
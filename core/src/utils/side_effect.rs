@@ -1,4 +1,16 @@
-use swc_ecma_ast::{Expr, ModuleDecl, ModuleItem, OptChainBase, OptChainExpr, PatOrExpr, Stmt};
+use std::collections::HashSet;
+
+use swc_common::{comments::Comments, Mark, Span, SyntaxContext};
+use swc_ecma_ast::{
+  Callee, Class, ClassMember, Decl, DefaultDecl, Expr, ExprOrSpread, Ident, Lit, MemberExpr,
+  MemberProp, ModuleDecl, ModuleItem, ObjectLit, OptChainBase, OptChainExpr, Pat, PatOrExpr, Prop,
+  PropName, PropOrSpread, Stmt, UnaryOp, VarDecl, VarDeclKind, VarDeclOrExpr, VarDeclOrPat,
+};
+
+use crate::{
+  ext::SyntaxContextExt,
+  types::{NormalizedTreeshakingOptions, PropertyReadSideEffects},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SideEffect {
@@ -8,121 +20,480 @@ pub enum SideEffect {
   VisitThis,
   NonTopLevel,
   VisitGlobalVar,
+  WriteProp,
+  WriteVar,
   Import,
 }
 
-fn detect_side_effect_of_expr(expr: &Expr) -> Option<SideEffect> {
+// Globals whose static properties could be read without side effects, such as `Math.PI`.
+const KNOWN_GLOBAL_OBJECTS: &[&str] = &[
+  "Array", "JSON", "Math", "Number", "Object", "Reflect", "String", "Symbol",
+];
+
+// Functions which don't have side effects when they are called with arguments without side effects.
+// Every function of `Math` is pure as well.
+const PURE_GLOBAL_FUNCTIONS: &[&str] = &[
+  "Array.from",
+  "Array.isArray",
+  "Array.of",
+  "BigInt",
+  "Boolean",
+  "Date",
+  "Number",
+  "Number.isFinite",
+  "Number.isInteger",
+  "Number.isNaN",
+  "Number.isSafeInteger",
+  "Number.parseFloat",
+  "Number.parseInt",
+  "Object",
+  "Object.create",
+  "Object.entries",
+  "Object.freeze",
+  "Object.getOwnPropertyDescriptor",
+  "Object.getOwnPropertyDescriptors",
+  "Object.getOwnPropertyNames",
+  "Object.getOwnPropertySymbols",
+  "Object.getPrototypeOf",
+  "Object.is",
+  "Object.isExtensible",
+  "Object.isFrozen",
+  "Object.isSealed",
+  "Object.keys",
+  "Object.values",
+  "String",
+  "String.fromCharCode",
+  "String.fromCodePoint",
+  "String.raw",
+  "Symbol",
+  "Symbol.for",
+  "Symbol.keyFor",
+  "decodeURI",
+  "decodeURIComponent",
+  "encodeURI",
+  "encodeURIComponent",
+  "escape",
+  "isFinite",
+  "isNaN",
+  "parseFloat",
+  "parseInt",
+  "unescape",
+];
+
+// Constructors which don't have side effects when they are called with arguments without side effects.
+const PURE_GLOBAL_CONSTRUCTORS: &[&str] = &[
+  "Array",
+  "ArrayBuffer",
+  "Boolean",
+  "DataView",
+  "Date",
+  "Error",
+  "EvalError",
+  "Float32Array",
+  "Float64Array",
+  "Int16Array",
+  "Int32Array",
+  "Int8Array",
+  "Map",
+  "Number",
+  "Object",
+  "RangeError",
+  "ReferenceError",
+  "RegExp",
+  "Set",
+  "String",
+  "SyntaxError",
+  "TypeError",
+  "URIError",
+  "Uint16Array",
+  "Uint32Array",
+  "Uint8Array",
+  "Uint8ClampedArray",
+  "WeakMap",
+  "WeakSet",
+];
+
+// The scanner leaves identifiers which are not declared in the module without a mark.
+#[inline]
+fn as_global_name(expr: &Expr) -> Option<&str> {
   match expr {
-    Expr::This(_) => Some(SideEffect::VisitThis),
-    Expr::Array(array_lit) => array_lit.elems.iter().find_map(|expr_or_spread| {
-      expr_or_spread
-        .as_ref()
-        .and_then(|exp| detect_side_effect_of_expr(exp.expr.as_ref()))
-    }),
-    Expr::Object(_) => Some(SideEffect::Todo),
+    Expr::Ident(Ident { sym, span, .. }) if span.ctxt == SyntaxContext::empty() => Some(&**sym),
+    _ => None,
+  }
+}
+
+// `Math.max` => `Some("Math.max")`
+fn as_global_path(expr: &Expr) -> Option<String> {
+  match expr {
+    Expr::Member(MemberExpr {
+      obj,
+      prop: MemberProp::Ident(prop),
+      ..
+    }) => as_global_name(obj).map(|obj| format!("{}.{}", obj, prop.sym)),
+    _ => as_global_name(expr).map(|name| name.to_string()),
+  }
+}
+
+// Globals which exist in every environment, so reading them doesn't throw.
+const SAFE_GLOBALS: &[&str] = &[
+  "AggregateError",
+  "Atomics",
+  "BigInt64Array",
+  "BigUint64Array",
+  "Function",
+  "Infinity",
+  "Intl",
+  "NaN",
+  "Promise",
+  "Proxy",
+  "SharedArrayBuffer",
+  "WeakRef",
+  "clearInterval",
+  "clearTimeout",
+  "console",
+  "eval",
+  "globalThis",
+  "queueMicrotask",
+  "setInterval",
+  "setTimeout",
+  "undefined",
+];
+
+fn is_known_global(name: &str) -> bool {
+  SAFE_GLOBALS.contains(&name)
+    || KNOWN_GLOBAL_OBJECTS.contains(&name)
+    || PURE_GLOBAL_FUNCTIONS.contains(&name)
+    || PURE_GLOBAL_CONSTRUCTORS.contains(&name)
+}
+
+fn is_pure_global_function(callee: &Expr) -> bool {
+  as_global_path(callee).map_or(false, |path| {
+    path.starts_with("Math.") || PURE_GLOBAL_FUNCTIONS.contains(&path.as_str())
+  })
+}
+
+struct SideEffectDetector<'a> {
+  options: &'a NormalizedTreeshakingOptions,
+  comments: &'a dyn Comments,
+  top_level_marks: &'a HashSet<Mark>,
+  // Functions declared in the bundle which are called by the statement.
+  callees: &'a mut HashSet<Mark>,
+}
 
-    Expr::Fn(_) => None,
+impl<'a> SideEffectDetector<'a> {
+  #[inline]
+  fn has_pure_annotation(&self, span: Span) -> bool {
+    self.options.annotations && self.comments.has_flag(span.lo, "PURE")
+  }
 
-    Expr::Unary(_) => Some(SideEffect::Todo),
+  // Reading an unknown global throws a `ReferenceError` if it doesn't exist.
+  fn detect_ident(&self, ident: &Ident) -> Option<SideEffect> {
+    let is_unknown_global =
+      ident.span.ctxt == SyntaxContext::empty() && !is_known_global(&ident.sym);
+    (self.options.unknown_global_side_effects && is_unknown_global)
+      .then(|| SideEffect::VisitGlobalVar)
+  }
 
-    // `++v`, `--v`, `v++`, `v--`
-    Expr::Update(update_expr) => detect_side_effect_of_expr(update_expr.arg.as_ref()),
+  // Writes to globals and top-level bindings are visible to other statements. Only bindings local to
+  // the statement, such as `for (let i = 0; i < 3; i++) {}`, can be written freely.
+  fn detect_write_ident(&self, ident: &Ident) -> Option<SideEffect> {
+    let ctxt = ident.span.ctxt;
+    (ctxt == SyntaxContext::empty() || self.top_level_marks.contains(&ctxt.as_mark()))
+      .then(|| SideEffect::WriteVar)
+  }
+
+  // Assigning a property might trigger a setter, and mutates an object which might be visible outside
+  // of the statement, such as `Object.polyfill = ...`.
+  fn detect_write(&mut self, target: &Expr) -> Option<SideEffect> {
+    match target {
+      Expr::Ident(ident) => self.detect_write_ident(ident),
+      Expr::Paren(paren_expr) => self.detect_write(paren_expr.expr.as_ref()),
+      Expr::Member(_) | Expr::SuperProp(_) | Expr::OptChain(_) => Some(SideEffect::WriteProp),
+      _ => Some(SideEffect::Todo),
+    }
+  }
 
-    Expr::Bin(bin_expr) => [bin_expr.left.as_ref(), bin_expr.right.as_ref()]
+  fn detect_assign_target(&mut self, target: &PatOrExpr) -> Option<SideEffect> {
+    match target {
+      PatOrExpr::Expr(expr) => self.detect_write(expr.as_ref()),
+      PatOrExpr::Pat(pat) => match pat.as_ref() {
+        Pat::Ident(binding) => self.detect_write_ident(&binding.id),
+        Pat::Expr(expr) => self.detect_write(expr.as_ref()),
+        _ => Some(SideEffect::Todo),
+      },
+    }
+  }
+
+  fn detect_expr(&mut self, expr: &Expr) -> Option<SideEffect> {
+    match expr {
+      Expr::This(_) => Some(SideEffect::VisitThis),
+      Expr::Array(array_lit) => array_lit.elems.iter().find_map(|expr_or_spread| {
+        expr_or_spread
+          .as_ref()
+          .and_then(|exp| self.detect_expr_or_spread(exp))
+      }),
+      Expr::Object(object_lit) => self.detect_object_lit(object_lit),
+
+      Expr::Fn(_) => None,
+
+      Expr::Unary(unary_expr) => match unary_expr.op {
+        // `delete foo.bar`
+        UnaryOp::Delete => Some(SideEffect::VisitProp),
+        // `typeof foo` doesn't throw even if `foo` is not declared.
+        UnaryOp::TypeOf if matches!(unary_expr.arg.as_ref(), Expr::Ident(_)) => None,
+        // `void 0`, `!foo`, `-foo`
+        _ => self.detect_expr(unary_expr.arg.as_ref()),
+      },
+
+      // `++v`, `--v`, `v++`, `v--`
+      Expr::Update(update_expr) => self.detect_write(update_expr.arg.as_ref()),
+
+      Expr::Bin(bin_expr) => [bin_expr.left.as_ref(), bin_expr.right.as_ref()]
+        .into_iter()
+        .find_map(|expr| self.detect_expr(expr)),
+
+      Expr::Assign(assign_expr) => self
+        .detect_assign_target(&assign_expr.left)
+        .or_else(|| self.detect_expr(assign_expr.right.as_ref())),
+      Expr::Member(member_expr) => self.detect_member_expr(member_expr),
+      Expr::SuperProp(_) => Some(SideEffect::VisitProp),
+
+      // true ? 'a' : 'b'
+      Expr::Cond(cond_expr) => [
+        cond_expr.test.as_ref(),
+        cond_expr.cons.as_ref(),
+        cond_expr.alt.as_ref(),
+      ]
       .into_iter()
-      .find_map(detect_side_effect_of_expr),
+      .find_map(|expr| self.detect_expr(expr)),
 
-    Expr::Assign(assign_expr) => match &assign_expr.left {
-      PatOrExpr::Expr(expr) => detect_side_effect_of_expr(expr.as_ref()),
-      PatOrExpr::Pat(_) => Some(SideEffect::Todo),
-    },
-    Expr::Member(_) => Some(SideEffect::VisitProp),
-    Expr::SuperProp(_) => Some(SideEffect::VisitProp),
+      Expr::Call(call_expr) => match &call_expr.callee {
+        // `/*#__PURE__*/ foo()`
+        _ if self.has_pure_annotation(call_expr.span) => self.detect_args(&call_expr.args),
+        // `Math.max(1, 2)`
+        Callee::Expr(callee) if is_pure_global_function(callee) => {
+          self.detect_args(&call_expr.args)
+        }
+        // Whether the callee is annotated with `__NO_SIDE_EFFECTS__` is only known after linking.
+        Callee::Expr(callee) => match callee.as_ref() {
+          Expr::Ident(ident) if ident.span.ctxt != SyntaxContext::empty() => {
+            self.detect_args(&call_expr.args).or_else(|| {
+              self.callees.insert(ident.span.ctxt.as_mark());
+              None
+            })
+          }
+          _ => Some(SideEffect::FnCall),
+        },
+        _ => Some(SideEffect::FnCall),
+      },
+      // `new Cat()`
+      Expr::New(new_expr) => match as_global_name(new_expr.callee.as_ref()) {
+        // `/*#__PURE__*/ new Cat()`
+        _ if self.has_pure_annotation(new_expr.span) => new_expr
+          .args
+          .as_ref()
+          .and_then(|args| self.detect_args(args)),
+        Some(name) if PURE_GLOBAL_CONSTRUCTORS.contains(&name) => new_expr
+          .args
+          .as_ref()
+          .and_then(|args| self.detect_args(args)),
+        _ => Some(SideEffect::FnCall),
+      },
 
-    // true ? 'a' : 'b'
-    Expr::Cond(cond_expr) => [
-      cond_expr.test.as_ref(),
-      cond_expr.cons.as_ref(),
-      cond_expr.alt.as_ref(),
-    ]
-    .into_iter()
-    .find_map(detect_side_effect_of_expr),
+      Expr::Seq(seq_expr) => seq_expr
+        .exprs
+        .iter()
+        .find_map(|expr| self.detect_expr(expr)),
 
-    Expr::Call(_) => Some(SideEffect::FnCall),
-    // `new Cat()`
-    Expr::New(_) => Some(SideEffect::FnCall),
+      Expr::Ident(ident) => self.detect_ident(ident),
 
-    Expr::Seq(seq_expr) => seq_expr
-      .exprs
-      .iter()
-      .find_map(|expr| detect_side_effect_of_expr(expr)),
+      Expr::Lit(_) => None,
 
-    Expr::Ident(_) => None,
+      Expr::Tpl(tpl) => tpl.exprs.iter().find_map(|expr| self.detect_expr(expr)),
 
-    Expr::Lit(_) => None,
+      Expr::TaggedTpl(_) => Some(SideEffect::FnCall),
 
-    Expr::Tpl(tpl) => tpl
-      .exprs
-      .iter()
-      .find_map(|expr| detect_side_effect_of_expr(expr)),
+      Expr::Arrow(_) => None,
 
-    Expr::TaggedTpl(_) => Some(SideEffect::FnCall),
+      Expr::Class(class_expr) => self.detect_class(&class_expr.class),
 
-    Expr::Arrow(_) => None,
+      Expr::Yield(_) => Some(SideEffect::Todo),
 
-    Expr::Class(_) => None,
+      Expr::MetaProp(_) => Some(SideEffect::Todo),
 
-    Expr::Yield(_) => Some(SideEffect::Todo),
+      Expr::Await(_) => Some(SideEffect::Todo),
 
-    Expr::MetaProp(_) => Some(SideEffect::Todo),
+      Expr::Paren(paren_expr) => self.detect_expr(paren_expr.expr.as_ref()),
 
-    Expr::Await(_) => Some(SideEffect::Todo),
+      Expr::JSXMember(_) => Some(SideEffect::Todo),
 
-    Expr::Paren(paren_expr) => detect_side_effect_of_expr(paren_expr.expr.as_ref()),
+      Expr::JSXNamespacedName(_) => Some(SideEffect::Todo),
 
-    Expr::JSXMember(_) => Some(SideEffect::Todo),
+      Expr::JSXEmpty(_) => Some(SideEffect::Todo),
 
-    Expr::JSXNamespacedName(_) => Some(SideEffect::Todo),
+      Expr::JSXElement(_) => Some(SideEffect::Todo),
 
-    Expr::JSXEmpty(_) => Some(SideEffect::Todo),
+      Expr::JSXFragment(_) => Some(SideEffect::Todo),
 
-    Expr::JSXElement(_) => Some(SideEffect::Todo),
+      Expr::TsTypeAssertion(_) => None,
 
-    Expr::JSXFragment(_) => Some(SideEffect::Todo),
+      Expr::TsConstAssertion(_) => None,
 
-    Expr::TsTypeAssertion(_) => None,
+      Expr::TsNonNull(_) => None,
 
-    Expr::TsConstAssertion(_) => None,
+      Expr::TsAs(_) => None,
 
-    Expr::TsNonNull(_) => None,
+      Expr::TsInstantiation(_) => None,
 
-    Expr::TsAs(_) => None,
+      Expr::PrivateName(_) => Some(SideEffect::Todo),
 
-    Expr::TsInstantiation(_) => None,
+      Expr::OptChain(OptChainExpr {
+        base: OptChainBase::Member(member),
+        ..
+      }) => self.detect_member_expr(member),
 
-    Expr::PrivateName(_) => Some(SideEffect::Todo),
+      Expr::OptChain(OptChainExpr {
+        base: OptChainBase::Call(_),
+        ..
+      }) => Some(SideEffect::FnCall),
 
-    Expr::OptChain(OptChainExpr {
-      base: OptChainBase::Member(member),
-      ..
-    }) => detect_side_effect_of_expr(&Expr::Member(member.clone())),
+      Expr::Invalid(_) => Some(SideEffect::Todo),
+    }
+  }
 
-    Expr::OptChain(OptChainExpr {
-      base: OptChainBase::Call(_),
-      ..
-    }) => None,
+  fn detect_expr_or_spread(&mut self, expr_or_spread: &ExprOrSpread) -> Option<SideEffect> {
+    if expr_or_spread.spread.is_some() {
+      // Spreading calls the iterator of the value.
+      Some(SideEffect::FnCall)
+    } else {
+      self.detect_expr(expr_or_spread.expr.as_ref())
+    }
+  }
 
-    Expr::Invalid(_) => Some(SideEffect::Todo),
+  fn detect_args(&mut self, args: &[ExprOrSpread]) -> Option<SideEffect> {
+    args.iter().find_map(|arg| self.detect_expr_or_spread(arg))
   }
-}
 
-// ESM environment
-pub fn detect_side_effect(item: &ModuleItem) -> Option<SideEffect> {
-  match item {
-    ModuleItem::ModuleDecl(ModuleDecl::Import(_)) => Some(SideEffect::Import),
-    ModuleItem::Stmt(stmt) => match stmt {
+  fn detect_prop_name(&mut self, prop_name: &PropName) -> Option<SideEffect> {
+    match prop_name {
+      PropName::Computed(computed) => self.detect_expr(computed.expr.as_ref()),
+      _ => None,
+    }
+  }
+
+  // `{ a: 1, [b]: c, ...d }`
+  fn detect_object_lit(&mut self, object_lit: &ObjectLit) -> Option<SideEffect> {
+    object_lit.props.iter().find_map(|prop| match prop {
+      // Spreading reads every property of the value.
+      PropOrSpread::Spread(spread) => self.detect_expr(spread.expr.as_ref()).or_else(|| {
+        (!self.is_property_read_pure(spread.expr.as_ref())).then(|| SideEffect::VisitProp)
+      }),
+      PropOrSpread::Prop(prop) => match prop.as_ref() {
+        Prop::Shorthand(ident) => self.detect_ident(ident),
+        Prop::KeyValue(key_value) => self
+          .detect_prop_name(&key_value.key)
+          .or_else(|| self.detect_expr(key_value.value.as_ref())),
+        Prop::Assign(_) => Some(SideEffect::Todo),
+        Prop::Getter(getter) => self.detect_prop_name(&getter.key),
+        Prop::Setter(setter) => self.detect_prop_name(&setter.key),
+        Prop::Method(method) => self.detect_prop_name(&method.key),
+      },
+    })
+  }
+
+  fn detect_member_expr(&mut self, member_expr: &MemberExpr) -> Option<SideEffect> {
+    self
+      .detect_expr(member_expr.obj.as_ref())
+      .or_else(|| match &member_expr.prop {
+        MemberProp::Computed(computed) => self.detect_expr(computed.expr.as_ref()),
+        _ => None,
+      })
+      .or_else(|| {
+        (!self.is_property_read_pure(member_expr.obj.as_ref())).then(|| SideEffect::VisitProp)
+      })
+  }
+
+  // Whether reading a property of `obj` can't trigger a getter or throw.
+  fn is_property_read_pure(&self, obj: &Expr) -> bool {
+    match self.options.property_read_side_effects {
+      PropertyReadSideEffects::Bool(false) => true,
+      PropertyReadSideEffects::Always => false,
+      PropertyReadSideEffects::Bool(true) => match obj {
+        Expr::Lit(Lit::Null(_)) => false,
+        Expr::Lit(_) | Expr::Array(_) | Expr::Tpl(_) | Expr::Fn(_) | Expr::Arrow(_) => true,
+        // Getters of the literal might be triggered.
+        Expr::Object(object_lit) => object_lit.props.iter().all(|prop| {
+          matches!(prop, PropOrSpread::Prop(prop) if !matches!(prop.as_ref(), Prop::Getter(_)))
+        }),
+        Expr::Paren(paren_expr) => self.is_property_read_pure(paren_expr.expr.as_ref()),
+        _ => as_global_name(obj).map_or(false, |name| KNOWN_GLOBAL_OBJECTS.contains(&name)),
+      },
+    }
+  }
+
+  // Defining a class evaluates its decorators, `extends` clause, computed keys, static properties and
+  // static blocks.
+  fn detect_class(&mut self, class: &Class) -> Option<SideEffect> {
+    if !class.decorators.is_empty() {
+      return Some(SideEffect::FnCall);
+    }
+    class
+      .super_class
+      .as_ref()
+      .and_then(|super_class| self.detect_expr(super_class.as_ref()))
+      .or_else(|| {
+        class.body.iter().find_map(|member| match member {
+          ClassMember::Method(method) => self.detect_prop_name(&method.key),
+          ClassMember::ClassProp(prop) => self.detect_prop_name(&prop.key).or_else(|| {
+            prop
+              .value
+              .as_ref()
+              .filter(|_| prop.is_static)
+              .and_then(|value| self.detect_expr(value.as_ref()))
+          }),
+          ClassMember::PrivateProp(prop) => prop
+            .value
+            .as_ref()
+            .filter(|_| prop.is_static)
+            .and_then(|value| self.detect_expr(value.as_ref())),
+          ClassMember::StaticBlock(block) => self.detect_stmts(&block.body.stmts),
+          _ => None,
+        })
+      })
+  }
+
+  fn detect_var_decl(&mut self, var_decl: &VarDecl) -> Option<SideEffect> {
+    var_decl.decls.iter().find_map(|decl| {
+      match &decl.name {
+        Pat::Ident(_) => None,
+        // Destructuring reads properties or calls the iterator.
+        _ => Some(SideEffect::VisitProp),
+      }
+      .or_else(|| {
+        decl
+          .init
+          .as_ref()
+          .and_then(|init| self.detect_expr(init.as_ref()))
+      })
+    })
+  }
+
+  fn detect_decl(&mut self, decl: &Decl) -> Option<SideEffect> {
+    match decl {
+      Decl::Var(var_decl) => self.detect_var_decl(var_decl),
+      Decl::Class(class_decl) => self.detect_class(&class_decl.class),
+      _ => None,
+    }
+  }
+
+  fn detect_stmts(&mut self, stmts: &[Stmt]) -> Option<SideEffect> {
+    stmts.iter().find_map(|stmt| self.detect_stmt(stmt))
+  }
+
+  fn detect_stmt(&mut self, stmt: &Stmt) -> Option<SideEffect> {
+    match stmt {
       // `{ }`
-      Stmt::Block(_) => Some(SideEffect::NonTopLevel),
+      Stmt::Block(block) => self.detect_stmts(&block.stmts),
       // `;`
       Stmt::Empty(_) => None,
       // `debugger`
@@ -131,32 +502,210 @@ pub fn detect_side_effect(item: &ModuleItem) -> Option<SideEffect> {
       Stmt::With(_) => Some(SideEffect::Todo),
       // `return`
       Stmt::Return(_) => Some(SideEffect::Todo),
-      // s
-      Stmt::Labeled(_) => Some(SideEffect::Todo),
-
-      Stmt::Break(_) => Some(SideEffect::Todo),
-
-      Stmt::Continue(_) => Some(SideEffect::Todo),
-
-      Stmt::If(_) => Some(SideEffect::Todo),
-
-      Stmt::Switch(_) => Some(SideEffect::Todo),
+      // `label: for (;;) {}`
+      Stmt::Labeled(labeled) => self.detect_stmt(labeled.body.as_ref()),
+
+      Stmt::Break(_) => None,
+
+      Stmt::Continue(_) => None,
+
+      Stmt::If(if_stmt) => self
+        .detect_expr(if_stmt.test.as_ref())
+        .or_else(|| self.detect_stmt(if_stmt.cons.as_ref()))
+        .or_else(|| {
+          if_stmt
+            .alt
+            .as_ref()
+            .and_then(|alt| self.detect_stmt(alt.as_ref()))
+        }),
+
+      Stmt::Switch(switch_stmt) => {
+        self
+          .detect_expr(switch_stmt.discriminant.as_ref())
+          .or_else(|| {
+            switch_stmt.cases.iter().find_map(|case| {
+              case
+                .test
+                .as_ref()
+                .and_then(|test| self.detect_expr(test.as_ref()))
+                .or_else(|| case.cons.iter().find_map(|stmt| self.detect_stmt(stmt)))
+            })
+          })
+      }
 
       Stmt::Throw(_) => Some(SideEffect::Todo),
-      Stmt::Try(_) => Some(SideEffect::Todo),
-
-      Stmt::While(_) => Some(SideEffect::Todo),
-
-      Stmt::DoWhile(_) => Some(SideEffect::Todo),
+      Stmt::Try(_) if self.options.try_catch_deoptimization => Some(SideEffect::Todo),
+      Stmt::Try(try_stmt) => self
+        .detect_stmts(&try_stmt.block.stmts)
+        .or_else(|| {
+          try_stmt
+            .handler
+            .as_ref()
+            .and_then(|handler| match &handler.param {
+              Some(Pat::Ident(_)) | None => self.detect_stmts(&handler.body.stmts),
+              // Destructuring reads properties or calls the iterator.
+              Some(_) => Some(SideEffect::VisitProp),
+            })
+        })
+        .or_else(|| {
+          try_stmt
+            .finalizer
+            .as_ref()
+            .and_then(|finalizer| self.detect_stmts(&finalizer.stmts))
+        }),
+
+      Stmt::While(while_stmt) => self
+        .detect_expr(while_stmt.test.as_ref())
+        .or_else(|| self.detect_stmt(while_stmt.body.as_ref())),
+
+      Stmt::DoWhile(do_while_stmt) => self
+        .detect_expr(do_while_stmt.test.as_ref())
+        .or_else(|| self.detect_stmt(do_while_stmt.body.as_ref())),
+
+      Stmt::For(for_stmt) => for_stmt
+        .init
+        .as_ref()
+        .and_then(|init| match init {
+          VarDeclOrExpr::VarDecl(var_decl) => self.detect_var_decl(var_decl),
+          VarDeclOrExpr::Expr(expr) => self.detect_expr(expr.as_ref()),
+        })
+        .or_else(|| {
+          [for_stmt.test.as_ref(), for_stmt.update.as_ref()]
+            .into_iter()
+            .flatten()
+            .find_map(|expr| self.detect_expr(expr.as_ref()))
+        })
+        .or_else(|| self.detect_stmt(for_stmt.body.as_ref())),
+
+      Stmt::ForIn(for_in_stmt) => match &for_in_stmt.left {
+        VarDeclOrPat::VarDecl(var_decl) => self.detect_var_decl(var_decl),
+        VarDeclOrPat::Pat(Pat::Ident(_)) => None,
+        VarDeclOrPat::Pat(_) => Some(SideEffect::Todo),
+      }
+      .or_else(|| self.detect_expr(for_in_stmt.right.as_ref()))
+      .or_else(|| self.detect_stmt(for_in_stmt.body.as_ref())),
+
+      // Iterating calls the iterator of the value.
+      Stmt::ForOf(_) => Some(SideEffect::FnCall),
+      Stmt::Decl(decl) => self.detect_decl(decl),
+      Stmt::Expr(expr_stmt) => self.detect_expr(expr_stmt.expr.as_ref()),
+    }
+  }
+}
 
-      Stmt::For(_) => Some(SideEffect::Todo),
+// ESM environment
+// `top_level_marks` are the marks of bindings declared or imported at the top level of the module.
+// Calls of functions declared in the bundle are collected into `callees`. They are pure only if the
+// functions are annotated with `__NO_SIDE_EFFECTS__`.
+pub fn detect_side_effect(
+  item: &ModuleItem,
+  options: &NormalizedTreeshakingOptions,
+  comments: &dyn Comments,
+  top_level_marks: &HashSet<Mark>,
+  callees: &mut HashSet<Mark>,
+) -> Option<SideEffect> {
+  let mut detector = SideEffectDetector {
+    options,
+    comments,
+    top_level_marks,
+    callees,
+  };
+  match item {
+    ModuleItem::ModuleDecl(ModuleDecl::Import(_)) => Some(SideEffect::Import),
+    // `export const a = foo()`
+    ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export_decl)) => {
+      detector.detect_decl(&export_decl.decl)
+    }
+    // `export default foo()`
+    ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export_default_expr)) => {
+      detector.detect_expr(export_default_expr.expr.as_ref())
+    }
+    // `export default class extends foo() {}`
+    ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(export_default_decl)) => {
+      match &export_default_decl.decl {
+        DefaultDecl::Class(class_expr) => detector.detect_class(&class_expr.class),
+        _ => None,
+      }
+    }
+    ModuleItem::Stmt(stmt) => detector.detect_stmt(stmt),
+    _ => None,
+  }
+}
 
-      Stmt::ForIn(_) => Some(SideEffect::Todo),
+// `/*#__NO_SIDE_EFFECTS__*/ function foo() {}`
+// `export const foo = /*#__NO_SIDE_EFFECTS__*/ () => {}`
+fn collect_no_side_effect_fns_of_decl(
+  decl: &Decl,
+  annotated: bool,
+  options: &NormalizedTreeshakingOptions,
+  comments: &dyn Comments,
+) -> Vec<Mark> {
+  let is_annotated = |span: Span| annotated || comments.has_flag(span.lo, "NO_SIDE_EFFECTS");
+  match decl {
+    Decl::Fn(fn_decl) if is_annotated(fn_decl.function.span) => {
+      vec![fn_decl.ident.span.ctxt.as_mark()]
+    }
+    // Calling a `var` function before its declaration throws a `TypeError`.
+    Decl::Var(var_decl)
+      if !(var_decl.kind == VarDeclKind::Var && options.correct_var_value_before_declaration) =>
+    {
+      var_decl
+        .decls
+        .iter()
+        .filter_map(|decl| match (&decl.name, decl.init.as_deref()) {
+          (Pat::Ident(binding), Some(Expr::Fn(fn_expr)))
+            if is_annotated(var_decl.span) || is_annotated(fn_expr.function.span) =>
+          {
+            Some(binding.id.span.ctxt.as_mark())
+          }
+          (Pat::Ident(binding), Some(Expr::Arrow(arrow_expr)))
+            if is_annotated(var_decl.span) || is_annotated(arrow_expr.span) =>
+          {
+            Some(binding.id.span.ctxt.as_mark())
+          }
+          _ => None,
+        })
+        .collect()
+    }
+    _ => vec![],
+  }
+}
 
-      Stmt::ForOf(_) => Some(SideEffect::Todo),
-      Stmt::Decl(_) => None,
-      Stmt::Expr(expr_stmt) => detect_side_effect_of_expr(expr_stmt.expr.as_ref()),
-    },
-    _ => None,
+// Functions annotated with `__NO_SIDE_EFFECTS__`, whose calls are pure if the arguments are pure.
+pub fn collect_no_side_effect_fns(
+  item: &ModuleItem,
+  options: &NormalizedTreeshakingOptions,
+  comments: &dyn Comments,
+) -> Vec<Mark> {
+  match item {
+    ModuleItem::Stmt(Stmt::Decl(decl)) => {
+      collect_no_side_effect_fns_of_decl(decl, false, options, comments)
+    }
+    // `/*#__NO_SIDE_EFFECTS__*/ export function foo() {}`
+    ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export_decl)) => {
+      collect_no_side_effect_fns_of_decl(
+        &export_decl.decl,
+        comments.has_flag(export_decl.span.lo, "NO_SIDE_EFFECTS"),
+        options,
+        comments,
+      )
+    }
+    // `export default /*#__NO_SIDE_EFFECTS__*/ function foo() {}`
+    ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(export_default_decl)) => {
+      match &export_default_decl.decl {
+        DefaultDecl::Fn(fn_expr)
+          if comments.has_flag(export_default_decl.span.lo, "NO_SIDE_EFFECTS")
+            || comments.has_flag(fn_expr.function.span.lo, "NO_SIDE_EFFECTS") =>
+        {
+          fn_expr
+            .ident
+            .iter()
+            .map(|ident| ident.span.ctxt.as_mark())
+            .collect()
+        }
+        _ => vec![],
+      }
+    }
+    _ => vec![],
   }
 }
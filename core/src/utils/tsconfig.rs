@@ -0,0 +1,217 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::utils::{is_relative, resolve_file};
+
+// `compilerOptions.baseUrl` and `compilerOptions.paths` of a `tsconfig.json`, with `extends` applied.
+#[derive(Debug, Default, Clone)]
+pub struct TsconfigPaths {
+  pub base_url: Option<String>,
+  // Directory of the tsconfig declaring `paths`. Used when there is no `baseUrl`.
+  pub paths_dir: Option<String>,
+  // (pattern, targets) such as `("@app/*", ["src/*"])`
+  pub paths: Vec<(String, Vec<String>)>,
+}
+
+impl TsconfigPaths {
+  pub fn from_file(path: &str) -> anyhow::Result<Self> {
+    let mut tsconfig = Self::default();
+    tsconfig.load(&nodejs_path::resolve!(path), &mut vec![])?;
+    Ok(tsconfig)
+  }
+
+  fn load(&mut self, path: &str, visited: &mut Vec<String>) -> anyhow::Result<()> {
+    if visited.iter().any(|p| p == path) {
+      anyhow::bail!("Circular `extends` found in {}", path);
+    }
+    visited.push(path.to_string());
+
+    let source = std::fs::read_to_string(path)?;
+    let json: Value = serde_json::from_str(&strip_json_comments(&source))?;
+    let dir = nodejs_path::dirname(path);
+
+    // Load the extended config first, so options declared here take precedence.
+    if let Some(extends) = json.get("extends").and_then(Value::as_str) {
+      let extended = resolve_extends(&dir, extends)
+        .ok_or_else(|| anyhow::anyhow!("Cannot find `{}` extended by {}", extends, path))?;
+      self.load(&extended, visited)?;
+    }
+
+    if let Some(compiler_options) = json.get("compilerOptions") {
+      if let Some(base_url) = compiler_options.get("baseUrl").and_then(Value::as_str) {
+        self.base_url = Some(nodejs_path::resolve!(&dir, base_url));
+      }
+      if let Some(paths) = compiler_options.get("paths").and_then(Value::as_object) {
+        self.paths = paths
+          .iter()
+          .map(|(pattern, targets)| {
+            let targets = targets
+              .as_array()
+              .map(|targets| {
+                targets
+                  .iter()
+                  .filter_map(Value::as_str)
+                  .map(|target| target.to_string())
+                  .collect()
+              })
+              .unwrap_or_default();
+            (pattern.clone(), targets)
+          })
+          .collect();
+        self.paths_dir = Some(dir);
+      }
+    }
+    Ok(())
+  }
+
+  // Resolve a bare specifier to a file the same way TypeScript does.
+  pub fn resolve(&self, source: &str) -> Option<String> {
+    if is_relative(source) || nodejs_path::is_absolute(source) {
+      return None;
+    }
+
+    if let Some(paths_base) = self.base_url.as_ref().or_else(|| self.paths_dir.as_ref()) {
+      // An exact pattern wins over wildcards, then the longest prefix wins.
+      let matched = self
+        .paths
+        .iter()
+        .filter_map(|(pattern, targets)| {
+          match_pattern(pattern, source).map(|wildcard| (pattern, wildcard, targets))
+        })
+        .max_by_key(|(pattern, _, _)| match pattern.find('*') {
+          Some(prefix_len) => (false, prefix_len),
+          None => (true, pattern.len()),
+        });
+
+      if let Some((_, wildcard, targets)) = matched {
+        let found = targets.iter().find_map(|target| {
+          resolve_file(&nodejs_path::resolve!(
+            paths_base,
+            &target.replacen('*', wildcard, 1)
+          ))
+        });
+        if found.is_some() {
+          return found;
+        }
+      }
+    }
+
+    self
+      .base_url
+      .as_ref()
+      .and_then(|base_url| resolve_file(&nodejs_path::resolve!(base_url, source)))
+  }
+}
+
+// Returns the part of `source` matched by `*`.
+fn match_pattern<'a>(pattern: &str, source: &'a str) -> Option<&'a str> {
+  match pattern.split_once('*') {
+    Some((prefix, suffix)) => {
+      if source.len() >= prefix.len() + suffix.len()
+        && source.starts_with(prefix)
+        && source.ends_with(suffix)
+      {
+        Some(&source[prefix.len()..source.len() - suffix.len()])
+      } else {
+        None
+      }
+    }
+    None => (pattern == source).then(|| ""),
+  }
+}
+
+fn resolve_extends(dir: &str, extends: &str) -> Option<String> {
+  let with_json_ext = |path: String| {
+    if Path::new(&path).is_file() {
+      Some(path)
+    } else {
+      let path = path + ".json";
+      Path::new(&path).is_file().then(|| path)
+    }
+  };
+
+  if is_relative(extends) || nodejs_path::is_absolute(extends) {
+    return with_json_ext(nodejs_path::resolve!(dir, extends));
+  }
+
+  // `extends` could point to a package, such as `@tsconfig/node16/tsconfig.json`.
+  Path::new(dir).ancestors().find_map(|ancestor| {
+    let path = ancestor.join("node_modules").join(extends);
+    let path = path.to_string_lossy().to_string();
+    with_json_ext(path.clone()).or_else(|| {
+      let path = nodejs_path::resolve!(&path, "tsconfig.json");
+      Path::new(&path).is_file().then(|| path)
+    })
+  })
+}
+
+// tsconfig.json allows comments and trailing commas, which `serde_json` doesn't.
+pub fn strip_json_comments(source: &str) -> String {
+  remove_trailing_commas(&remove_comments(source))
+}
+
+fn remove_comments(source: &str) -> String {
+  let chars = source.chars().collect::<Vec<_>>();
+  let mut output = String::with_capacity(source.len());
+  let mut in_string = false;
+  let mut i = 0;
+  while i < chars.len() {
+    let c = chars[i];
+    if in_string {
+      output.push(c);
+      if c == '\\' && i + 1 < chars.len() {
+        output.push(chars[i + 1]);
+        i += 1;
+      } else if c == '"' {
+        in_string = false;
+      }
+    } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+      while i < chars.len() && chars[i] != '\n' {
+        i += 1;
+      }
+      continue;
+    } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+      i += 2;
+      while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+        i += 1;
+      }
+      i += 2;
+      continue;
+    } else {
+      in_string = c == '"';
+      output.push(c);
+    }
+    i += 1;
+  }
+  output
+}
+
+fn remove_trailing_commas(source: &str) -> String {
+  let chars = source.chars().collect::<Vec<_>>();
+  let mut output = String::with_capacity(source.len());
+  let mut in_string = false;
+  let mut i = 0;
+  while i < chars.len() {
+    let c = chars[i];
+    if in_string {
+      output.push(c);
+      if c == '\\' && i + 1 < chars.len() {
+        output.push(chars[i + 1]);
+        i += 1;
+      } else if c == '"' {
+        in_string = false;
+      }
+    } else if c == ',' {
+      let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+      if !matches!(next, Some('}') | Some(']')) {
+        output.push(c);
+      }
+    } else {
+      in_string = c == '"';
+      output.push(c);
+    }
+    i += 1;
+  }
+  output
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+use swc_common::Span;
+
+use crate::error::{fmt_position, locate, Location};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningCode {
+  CircularDependency,
+  EmptyBundle,
+  Eval,
+  NamespaceConflict,
+  ThisIsUndefined,
+  UnusedExternalImport,
+}
+
+impl WarningCode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::CircularDependency => "CIRCULAR_DEPENDENCY",
+      Self::EmptyBundle => "EMPTY_BUNDLE",
+      Self::Eval => "EVAL",
+      Self::NamespaceConflict => "NAMESPACE_CONFLICT",
+      Self::ThisIsUndefined => "THIS_IS_UNDEFINED",
+      Self::UnusedExternalImport => "UNUSED_EXTERNAL_IMPORT",
+    }
+  }
+}
+
+impl fmt::Display for WarningCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+  pub code: WarningCode,
+  pub message: String,
+  pub id: Option<String>,
+  pub loc: Option<Location>,
+  pub frame: Option<String>,
+}
+
+impl Warning {
+  pub fn new(code: WarningCode, message: impl Into<String>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+      id: None,
+      loc: None,
+      frame: None,
+    }
+  }
+
+  pub fn with_id(mut self, id: impl Into<String>) -> Self {
+    self.id = Some(id.into());
+    self
+  }
+
+  pub fn with_span(mut self, span: Span) -> Self {
+    if let Some((loc, frame)) = locate(span) {
+      self.id.get_or_insert_with(|| loc.file.clone());
+      self.loc = Some(loc);
+      self.frame = Some(frame);
+    }
+    self
+  }
+}
+
+impl fmt::Display for Warning {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "(!) [{}] {}", self.code, self.message)?;
+    fmt_position(f, &self.id, &self.loc, &self.frame)
+  }
+}
+
+pub type WarningHandler = Box<dyn Fn(&Warning) + Send + Sync>;
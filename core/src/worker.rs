@@ -1,178 +1,232 @@
-use std::{
-  fs,
-  sync::{Arc, Mutex},
-};
+use std::{fs, sync::Arc};
 
-use crossbeam::{channel::Sender, queue::SegQueue};
-use dashmap::{DashMap, DashSet};
-use smol_str::SmolStr;
-use swc_common::Mark;
+use crossbeam::channel::Sender;
+use swc_common::comments::SingleThreadedComments;
 use swc_ecma_ast::{ModuleDecl, ModuleItem};
 use swc_ecma_visit::VisitMutWith;
-use thiserror::Error;
 
 use crate::{
+  error::RolldownError,
+  external_module::ExternalModule,
   graph::{Msg, Rel},
   module::Module,
+  plugin_driver::PluginDriver,
   scanner::{scope::BindType, Scanner},
-  symbol_box::SymbolBox,
-  types::ResolvedId,
-  utils::parse_file,
+  types::{NormalizedInputOptions, ResolvedId},
+  utils::{package_json::PackageJsonCache, parse_file},
 };
 
-#[derive(Error, Debug)]
-pub enum RolldownError {
-  #[error("[IO error `{0}`]")]
-  IO(std::io::Error),
-  #[error("[Crossbeam error `{0}`]")]
-  Channel(crossbeam::channel::SendError<Msg>),
-  #[error("[Mutex error]")]
-  Lock,
-}
-
 pub struct Worker {
-  pub symbol_box: Arc<Mutex<SymbolBox>>,
-  pub job_queue: Arc<SegQueue<ResolvedId>>,
   pub tx: Sender<Msg>,
-  pub processed_id: Arc<DashSet<SmolStr>>,
-  pub mark_to_stmt: Arc<DashMap<Mark, (SmolStr, usize)>>,
+  pub input_options: Arc<NormalizedInputOptions>,
+  pub plugin_driver: Arc<PluginDriver>,
+  pub package_json_cache: Arc<PackageJsonCache>,
 }
 
 impl Worker {
-  fn fetch_job(&self) -> Option<ResolvedId> {
-    self
-      .job_queue
-      .pop()
-      .filter(|resolved_id| !self.processed_id.contains(&resolved_id.id))
-      .map(|resolved_id| {
-        self.processed_id.insert(resolved_id.id.clone());
-        resolved_id
-      })
-  }
-
-  pub fn run(&mut self) -> Result<(), RolldownError> {
-    if let Some(resolved_id) = self.fetch_job() {
-      if resolved_id.external {
-        // TODO: external module
-      } else {
-        let mut module = Module::new(resolved_id.id.clone());
-        let id: &str = &resolved_id.id;
-        let source = fs::read_to_string(id).map_err(RolldownError::IO)?;
-        let mut ast = parse_file(source, &module.id);
-        self.pre_analyze_imported_module(&mut module, &ast);
+  pub fn run(&mut self, resolved_id: ResolvedId) -> Result<(), RolldownError> {
+    if resolved_id.external {
+      // External modules are registered by their importers. See `pre_analyze_imported_module`.
+    } else {
+      let mut module = Module::new(resolved_id.id.clone());
+      let id: &str = &resolved_id.id;
+      let (source, loaded_module_side_effects) = match self.plugin_driver.load(id) {
+        Some(loaded) => (loaded.code, loaded.module_side_effects),
+        None => (fs::read_to_string(id).map_err(RolldownError::IO)?, None),
+      };
+      module.module_side_effects =
+        loaded_module_side_effects.unwrap_or_else(|| self.has_module_side_effects(&resolved_id));
+      let comments = SingleThreadedComments::default();
+      let mut ast = match parse_file(source, &module.id, Some(&comments)) {
+        Ok(ast) => ast,
+        Err(errors) => {
+          // Other modules are still scanned, so that all broken files are reported at once.
+          return self
+            .tx
+            .send(Msg::Errors(errors))
+            .map_err(|_| RolldownError::Channel);
+        }
+      };
+      self.pre_analyze_imported_module(&mut module, &ast)?;
 
-        let mut scanner = Scanner::new(self.symbol_box.clone(), self.tx.clone());
-        ast.visit_mut_with(&mut scanner);
+      let mut scanner = Scanner::new(self.tx.clone(), self.input_options.clone(), comments);
+      ast.visit_mut_with(&mut scanner);
 
-        scanner
-          .import_infos
-          .iter()
-          .try_for_each(|(imported, info)| {
-            let resolved_id = module.resolve_id(imported);
-            self
-              .tx
-              .send(Msg::DependencyReference(
-                module.id.clone(),
-                resolved_id.id,
-                info.clone().into(),
-              ))
-              .map_err(RolldownError::Channel)
-          })?;
-        scanner
-          .re_export_infos
-          .iter()
-          .try_for_each(|(re_exported, info)| {
-            let resolved_id = module.resolve_id(re_exported);
-            self
-              .tx
-              .send(Msg::DependencyReference(
-                module.id.clone(),
-                resolved_id.id,
-                info.clone().into(),
-              ))
-              .map_err(RolldownError::Channel)
-          })?;
-        scanner
-          .export_all_sources
-          .iter()
-          .try_for_each(|re_exported| {
-            let resolved_id = module.resolve_id(&re_exported.0);
-            self
-              .tx
-              .send(Msg::DependencyReference(
-                module.id.clone(),
-                resolved_id.id,
-                Rel::ReExportAll(re_exported.1),
-              ))
-              .map_err(RolldownError::Channel)
-          })?;
+      std::mem::take(&mut scanner.warnings)
+        .into_iter()
+        .try_for_each(|warning| {
+          self
+            .tx
+            .send(Msg::Warning(warning.with_id(module.id.as_str())))
+            .map_err(|_| RolldownError::Channel)
+        })?;
+      if !scanner.errors.is_empty() {
+        return self
+          .tx
+          .send(Msg::Errors(std::mem::take(&mut scanner.errors)))
+          .map_err(|_| RolldownError::Channel);
+      }
 
-        module.local_exports = scanner.local_exports;
-        module.re_exports = scanner.re_exports;
-        module.re_export_all_sources = scanner
-          .export_all_sources
-          .into_iter()
-          .map(|s| s.0)
-          .collect();
-        {
-          let root_scope = scanner.stacks.into_iter().next().unwrap();
-          let declared_symbols = root_scope.declared_symbols;
-          let mut declared_symbols_kind = root_scope.declared_symbols_kind;
-          declared_symbols.into_iter().for_each(|(name, mark)| {
-            let bind_type = declared_symbols_kind.remove(&name).unwrap();
-            if BindType::Import == bind_type {
-              module.imported_symbols.insert(name, mark);
-            } else {
-              module.declared_symbols.insert(name, mark);
-            }
-          });
-        }
-        module.namespace.mark = self
-          .symbol_box
-          .lock()
-          .map_err(|_| RolldownError::Lock)?
-          .new_mark();
+      scanner
+        .import_infos
+        .iter()
+        .try_for_each(|(imported, info)| {
+          let resolved_id =
+            module.resolve_id(imported, &self.input_options, &self.plugin_driver)?;
+          self
+            .tx
+            .send(Msg::DependencyReference(
+              module.id.clone(),
+              resolved_id.id,
+              info.clone().into(),
+            ))
+            .map_err(|_| RolldownError::Channel)
+        })?;
+      scanner
+        .re_export_infos
+        .iter()
+        .try_for_each(|(re_exported, info)| {
+          let resolved_id =
+            module.resolve_id(re_exported, &self.input_options, &self.plugin_driver)?;
+          self
+            .tx
+            .send(Msg::DependencyReference(
+              module.id.clone(),
+              resolved_id.id,
+              info.clone().into(),
+            ))
+            .map_err(|_| RolldownError::Channel)
+        })?;
+      scanner
+        .export_all_sources
+        .iter()
+        .try_for_each(|re_exported| {
+          let resolved_id =
+            module.resolve_id(&re_exported.0, &self.input_options, &self.plugin_driver)?;
+          self
+            .tx
+            .send(Msg::DependencyReference(
+              module.id.clone(),
+              resolved_id.id,
+              Rel::ReExportAll(re_exported.1),
+            ))
+            .map_err(|_| RolldownError::Channel)
+        })?;
 
-        module.set_statements(ast, scanner.statement_infos, self.mark_to_stmt.clone());
+      module.local_exports = scanner.local_exports;
+      module.re_exports = scanner.re_exports;
+      module.no_side_effect_fns = scanner.no_side_effect_fns;
+      module.global_reads = scanner.global_reads;
+      module.re_export_all_sources = scanner
+        .export_all_sources
+        .into_iter()
+        .map(|s| s.0)
+        .collect();
+      if let Some(root_scope) = scanner.stacks.into_iter().next() {
+        let declared_symbols = root_scope.declared_symbols;
+        let mut declared_symbols_kind = root_scope.declared_symbols_kind;
+        declared_symbols.into_iter().for_each(|(name, mark)| {
+          if declared_symbols_kind.remove(&name) == Some(BindType::Import) {
+            module.imported_symbols.insert(name, mark);
+          } else {
+            module.declared_symbols.insert(name, mark);
+          }
+        });
+      }
+      module.namespace.mark = scanner.marks.new_mark();
+      module.mark_count = scanner.marks.count();
 
-        module.bind_local_references(&mut self.symbol_box.lock().unwrap());
+      module.set_statements(ast, scanner.statement_infos);
 
-        module.link_local_exports();
+      module.link_local_exports();
 
-        log::debug!("[worker]: emit module {:#?}", module);
-        self
-          .tx
-          .send(Msg::NewMod(Box::new(module)))
-          .map_err(RolldownError::Channel)?;
-      }
+      log::debug!("[worker]: emit module {:#?}", module);
+      self
+        .tx
+        .send(Msg::NewMod(Box::new(module)))
+        .map_err(|_| RolldownError::Channel)?;
     }
     Ok(())
   }
 
+  // Plugins take precedence over the `sideEffects` field of `package.json`, which takes precedence over
+  // the `module_side_effects` option of `treeshake`.
+  fn has_module_side_effects(&self, resolved_id: &ResolvedId) -> bool {
+    resolved_id
+      .module_side_effects
+      .or_else(|| {
+        let id = resolved_id.id.as_str();
+        if resolved_id.external || !nodejs_path::is_absolute(id) {
+          return None;
+        }
+        self
+          .package_json_cache
+          .find_up(&nodejs_path::dirname(id), &mut |path| {
+            std::fs::read_to_string(path).ok()
+          })
+          .and_then(|package| package.has_side_effects(id))
+      })
+      .unwrap_or_else(|| {
+        self
+          .input_options
+          .treeshake
+          .as_ref()
+          .map_or(true, |treeshake| {
+            treeshake
+              .module_side_effects
+              .resolve(&resolved_id.id, resolved_id.external)
+          })
+      })
+  }
+
   // Fast path for analyzing static import and export.
-  pub fn pre_analyze_imported_module(&self, module: &mut Module, ast: &swc_ecma_ast::Module) {
-    ast.body.iter().for_each(|module_item| {
+  pub fn pre_analyze_imported_module(
+    &self,
+    module: &mut Module,
+    ast: &swc_ecma_ast::Module,
+  ) -> Result<(), RolldownError> {
+    ast.body.iter().try_for_each(|module_item| {
       if let ModuleItem::ModuleDecl(module_decl) = module_item {
         let mut depended = None;
         match module_decl {
           ModuleDecl::Import(import_decl) => {
-            depended = Some(&import_decl.src.value);
+            depended = Some(&import_decl.src);
           }
           ModuleDecl::ExportNamed(node) => {
             if let Some(source_node) = &node.src {
-              depended = Some(&source_node.value);
+              depended = Some(source_node);
             }
           }
           ModuleDecl::ExportAll(node) => {
-            depended = Some(&node.src.value);
+            depended = Some(&node.src);
           }
           _ => {}
         }
         if let Some(depended) = depended {
-          let resolved_id = module.resolve_id(depended);
-          self.job_queue.push(resolved_id);
+          let resolved_id = module
+            .resolve_id(&depended.value, &self.input_options, &self.plugin_driver)
+            .map_err(|err| err.with_span(depended.span))?;
+          if resolved_id.external {
+            let renormalize_render_path = self
+              .input_options
+              .make_absolute_externals_relative
+              .should_renormalize(&resolved_id.id, &depended.value);
+            let mut external_module =
+              ExternalModule::new(resolved_id.id.to_string(), renormalize_render_path);
+            external_module.module_side_effects = self.has_module_side_effects(&resolved_id);
+            self
+              .tx
+              .send(Msg::NewExtMod(external_module))
+              .map_err(|_| RolldownError::Channel)?;
+          } else {
+            self
+              .tx
+              .send(Msg::NewJob(resolved_id))
+              .map_err(|_| RolldownError::Channel)?;
+          }
         }
       }
-    });
+      Ok(())
+    })
   }
 }
@@ -0,0 +1,54 @@
+use rolldown::{
+  bundle::Bundle,
+  graph::Graph,
+  types::{Alias, AliasFind, NormalizedInputOptions},
+  utils::tsconfig::TsconfigPaths,
+};
+
+#[test]
+fn alias_and_tsconfig_paths() {
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/alias/index.js".to_owned()],
+    alias: vec![Alias {
+      find: AliasFind::Prefix("~".to_string()),
+      replacement: nodejs_path::resolve!("./tests/fixtures/alias/src"),
+    }],
+    tsconfig: Some(TsconfigPaths::from_file("./tests/fixtures/alias/tsconfig.json").unwrap()),
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, Default::default());
+  let code = bundle.generate().into_values().next().unwrap().code;
+
+  assert!(code.contains("function add("), "{}", code);
+  assert!(code.contains("const VERSION"), "{}", code);
+  assert!(code.contains("const greet"), "{}", code);
+  assert!(!code.contains("import "), "{}", code);
+}
+
+#[test]
+fn alias_patterns() {
+  let exact = Alias {
+    find: AliasFind::Exact("react".to_string()),
+    replacement: "preact/compat".to_string(),
+  };
+  assert_eq!(exact.resolve("react"), Some("preact/compat".to_string()));
+  assert_eq!(exact.resolve("react/jsx-runtime"), None);
+
+  let prefix = Alias {
+    find: AliasFind::Prefix("@app".to_string()),
+    replacement: "/src".to_string(),
+  };
+  assert_eq!(prefix.resolve("@app"), Some("/src".to_string()));
+  assert_eq!(prefix.resolve("@app/utils"), Some("/src/utils".to_string()));
+  assert_eq!(prefix.resolve("@application"), None);
+
+  let regex = Alias {
+    find: AliasFind::Regex(regex::Regex::new(r"^lodash/(.*)$").unwrap()),
+    replacement: "lodash-es/$1".to_string(),
+  };
+  assert_eq!(
+    regex.resolve("lodash/map"),
+    Some("lodash-es/map".to_string())
+  );
+}
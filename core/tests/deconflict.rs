@@ -0,0 +1,13 @@
+use rolldown::{bundle::Bundle, graph::Graph, types::NormalizedOutputOptions};
+
+#[test]
+fn declarations_do_not_shadow_globals() {
+  let mut graph = Graph::from_single_entry("./tests/fixtures/shadowed-globals/index.js".to_owned());
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, NormalizedOutputOptions::default());
+  let output = bundle.generate();
+  let code = &output.values().next().unwrap().code;
+  assert!(code.contains("const value$0"), "{}", code);
+  assert!(code.contains("=> value$0"), "{}", code);
+  assert!(code.contains("console.log(value,"), "{}", code);
+}
@@ -0,0 +1,149 @@
+use rolldown::{
+  error::{BuildError, ErrorCode, RolldownError},
+  plugin_driver::Plugin,
+  types::{LoadResult, NormalizedInputOptions, NormalizedOutputOptions},
+  utils::parse_file,
+  RolldownBuild,
+};
+
+fn build(input: &str) -> Result<RolldownBuild, RolldownError> {
+  RolldownBuild::new(NormalizedInputOptions {
+    input: vec![input.to_owned()],
+    ..Default::default()
+  })
+}
+
+fn expect_build_error(result: Result<RolldownBuild, RolldownError>) -> BuildError {
+  match result {
+    Err(RolldownError::Build(err)) => err,
+    Err(err) => panic!("unexpected error: {}", err),
+    Ok(_) => panic!("the build should fail"),
+  }
+}
+
+#[test]
+fn parse_error_with_code_frame() {
+  let errors = parse_file(
+    "const a = 1;\nconsole.log(a;\nexport default a;\n".to_owned(),
+    "parse-error.js",
+    None,
+  )
+  .unwrap_err();
+  assert_eq!(errors.len(), 1);
+  let err = &errors[0];
+  assert_eq!(err.code, ErrorCode::ParseError);
+  assert_eq!(err.id.as_deref(), Some("parse-error.js"));
+  let loc = err.loc.clone().unwrap();
+  assert_eq!((loc.line, loc.column), (2, 13));
+  let frame = err.frame.clone().unwrap();
+  assert!(frame.contains("2: console.log(a;"), "{}", frame);
+  assert!(frame.contains("               ^"), "{}", frame);
+  assert!(err.to_string().starts_with("[PARSE_ERROR]"), "{}", err);
+}
+
+#[test]
+fn parse_errors_of_all_modules() {
+  let errors = match build("./tests/fixtures/errors/parse-errors/index.js") {
+    Err(RolldownError::Errors(errors)) => errors,
+    Err(err) => panic!("unexpected error: {}", err),
+    Ok(_) => panic!("the build should fail"),
+  };
+  let ids = errors
+    .iter()
+    .map(|err| {
+      assert_eq!(err.code, ErrorCode::ParseError);
+      assert!(err.frame.is_some());
+      err.id.clone().unwrap()
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(ids.len(), 2, "{:?}", ids);
+  assert!(ids[0].ends_with("parse-errors/a.js"));
+  assert!(ids[1].ends_with("parse-errors/c.js"));
+}
+
+#[test]
+fn missing_export() {
+  let err = expect_build_error(build("./tests/fixtures/errors/missing-export/index.js"));
+  assert_eq!(err.code, ErrorCode::MissingExport);
+  assert!(
+    err.message.contains("\"answer\" is not exported by"),
+    "{}",
+    err
+  );
+  assert!(err.id.unwrap().ends_with("missing-export/index.js"));
+  assert_eq!(err.loc.map(|loc| (loc.line, loc.column)), Some((1, 9)));
+}
+
+#[test]
+fn unresolved_entry() {
+  let err = expect_build_error(build("./tests/fixtures/errors/not-exist.js"));
+  assert_eq!(err.code, ErrorCode::UnresolvedEntry);
+}
+
+#[test]
+fn unresolved_import_from_worker() {
+  let err = expect_build_error(build("./tests/fixtures/errors/unresolved-import/index.js"));
+  assert_eq!(err.code, ErrorCode::UnresolvedImport);
+  assert!(err.message.contains("./missing.js"), "{}", err);
+  assert!(err.id.unwrap().ends_with("unresolved-import/foo.js"));
+  assert_eq!(err.loc.map(|loc| (loc.line, loc.column)), Some((1, 20)));
+}
+
+#[test]
+fn string_literal_export_name() {
+  let errors = match build("./tests/fixtures/errors/string-export-name/index.js") {
+    Err(RolldownError::Errors(errors)) => errors,
+    Err(err) => panic!("unexpected error: {}", err),
+    Ok(_) => panic!("the build should fail"),
+  };
+  assert_eq!(errors.len(), 1);
+  assert_eq!(errors[0].code, ErrorCode::UnsupportedSyntax);
+  assert!(errors[0].message.contains("\"foo-bar\""), "{}", errors[0]);
+  assert_eq!(
+    errors[0].loc.as_ref().map(|loc| (loc.line, loc.column)),
+    Some((3, 16))
+  );
+}
+
+struct PanickingLoader;
+
+impl Plugin for PanickingLoader {
+  fn get_name(&self) -> &'static str {
+    "panicking-loader"
+  }
+
+  fn load(&mut self, id: &str) -> LoadResult {
+    if id.ends_with("foo.js") {
+      panic!("cannot load {}", id);
+    }
+    None
+  }
+}
+
+#[test]
+fn panicking_worker_fails_the_build() {
+  let result = RolldownBuild::new(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/errors/missing-export/index.js".to_owned()],
+    plugins: vec![Box::new(PanickingLoader)],
+    ..Default::default()
+  });
+  match result {
+    Err(RolldownError::Panic(id, message)) => {
+      assert!(id.ends_with("missing-export/foo.js"), "{}", id);
+      assert!(message.starts_with("cannot load"), "{}", message);
+    }
+    Err(err) => panic!("unexpected error: {}", err),
+    Ok(_) => panic!("the build should fail"),
+  }
+}
+
+#[test]
+fn write_without_output_target() {
+  let result = build("./tests/fixtures/errors/missing-export/foo.js")
+    .unwrap()
+    .write(NormalizedOutputOptions::default());
+  match result {
+    Err(RolldownError::Build(err)) => assert_eq!(err.code, ErrorCode::MissingOption),
+    _ => panic!("`write` should require `file` or `dir`"),
+  }
+}
@@ -0,0 +1,60 @@
+use std::sync::{Arc, Mutex};
+
+use rolldown::{
+  bundle::Bundle,
+  graph::Graph,
+  types::{NormalizedInputOptions, NormalizedOutputOptions},
+  warning::{Warning, WarningCode},
+};
+
+#[test]
+fn execute_all_entries_in_order() {
+  let warnings = Arc::new(Mutex::new(vec![]));
+  let collected = warnings.clone();
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: ["entry-a", "entry-b", "entry-c"]
+      .iter()
+      .map(|name| format!("./tests/fixtures/multiple-entries/{}.js", name))
+      .collect(),
+    on_warn: Some(Box::new(move |warning: &Warning| {
+      collected.lock().unwrap().push(warning.clone())
+    })),
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, NormalizedOutputOptions::default());
+  let output = bundle.generate();
+  let code = &output.values().next().unwrap().code;
+
+  let positions = [
+    "'shared module'",
+    "'entry a'",
+    "'cycle b'",
+    "'cycle a'",
+    "'entry b'",
+    "'entry c'",
+  ]
+  .iter()
+  .map(|log| {
+    code
+      .find(log)
+      .unwrap_or_else(|| panic!("{} is missing\n{}", log, code))
+  })
+  .collect::<Vec<_>>();
+  assert!(
+    positions.windows(2).all(|pair| pair[0] < pair[1]),
+    "{}",
+    code
+  );
+
+  let warnings = warnings.lock().unwrap();
+  let circular = warnings
+    .iter()
+    .filter(|w| w.code == WarningCode::CircularDependency)
+    .collect::<Vec<_>>();
+  assert_eq!(circular.len(), 1, "{:?}", warnings);
+  assert_eq!(
+    circular[0].message,
+    "Circular dependency: tests/fixtures/multiple-entries/cycle-a.js -> tests/fixtures/multiple-entries/cycle-b.js -> tests/fixtures/multiple-entries/cycle-a.js"
+  );
+}
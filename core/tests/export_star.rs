@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+
+use rolldown::{
+  graph::Graph,
+  types::NormalizedInputOptions,
+  warning::{Warning, WarningCode},
+};
+
+#[test]
+fn exclude_conflicting_star_exports() {
+  let warnings = Arc::new(Mutex::new(vec![]));
+  let collected = warnings.clone();
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/export-star/index.js".to_owned()],
+    on_warn: Some(Box::new(move |warning: &Warning| {
+      collected.lock().unwrap().push(warning.clone())
+    })),
+    ..Default::default()
+  });
+  graph.build().unwrap();
+
+  let module = |name: &str| {
+    graph
+      .module_by_id
+      .values()
+      .find(|module| module.id.ends_with(name))
+      .unwrap()
+  };
+  let exports_of = |name: &str| {
+    let mut names = module(name)
+      .exports
+      .keys()
+      .filter(|name| *name != "*")
+      .map(|name| name.to_string())
+      .collect::<Vec<_>>();
+    names.sort();
+    names
+  };
+  // `default` is never re-exported, and `conflict` is ambiguous.
+  assert_eq!(exports_of("barrel.js"), vec!["deep", "fromA", "local"]);
+  // Ambiguous names stay excluded through chained barrels, even if `./other` provides one.
+  assert_eq!(exports_of("outer.js"), vec!["deep", "fromA", "local"]);
+  // The local export shadows the star export.
+  let outer = module("outer.js");
+  let mut symbol_box = graph.symbol_box.lock().unwrap();
+  assert_eq!(
+    symbol_box.find_root(*outer.exports.get(&"local".into()).unwrap()),
+    symbol_box.find_root(*outer.declared_symbols.get(&"local".into()).unwrap())
+  );
+
+  let warnings = warnings.lock().unwrap();
+  let conflicts = warnings
+    .iter()
+    .filter(|w| w.code == WarningCode::NamespaceConflict)
+    .collect::<Vec<_>>();
+  assert_eq!(conflicts.len(), 1, "{:?}", warnings);
+  assert_eq!(
+    conflicts[0].message,
+    "Conflicting namespaces: \"tests/fixtures/export-star/barrel.js\" re-exports \"conflict\" from one of the modules \"tests/fixtures/export-star/a.js\" and \"tests/fixtures/export-star/b.js\" (will be ignored)"
+  );
+}
+
+#[test]
+fn star_exports_of_the_same_binding_are_not_conflicts() {
+  let warnings = Arc::new(Mutex::new(vec![]));
+  let collected = warnings.clone();
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/export-star-same-binding/index.js".to_owned()],
+    on_warn: Some(Box::new(move |warning: &Warning| {
+      collected.lock().unwrap().push(warning.clone())
+    })),
+    ..Default::default()
+  });
+  // `x` would be missing if it were excluded as a conflict.
+  graph.build().unwrap();
+
+  let warnings = warnings.lock().unwrap();
+  assert!(
+    warnings
+      .iter()
+      .all(|w| w.code != WarningCode::NamespaceConflict),
+    "{:?}",
+    warnings
+  );
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use rolldown::{
+  bundle::Bundle,
+  graph::Graph,
+  plugin_driver::Plugin,
+  types::{
+    MakeAbsoluteExternalsRelative, NormalizedInputOptions, NormalizedOutputOptions, OptionsPaths,
+    ResolveIdResult, ResolvedId,
+  },
+};
+
+// Resolves `./vendor/*` to absolute ids and marks them as external.
+struct VendorExternal;
+
+impl Plugin for VendorExternal {
+  fn get_name(&self) -> &'static str {
+    "vendor-external"
+  }
+
+  fn resolve_id(&mut self, source: &str, importer: Option<&str>) -> ResolveIdResult {
+    let importer = importer?;
+    if source.starts_with("./vendor/") {
+      let id = nodejs_path::resolve!(&nodejs_path::dirname(importer), source) + ".js";
+      Some(ResolvedId::new(id.into(), true))
+    } else {
+      None
+    }
+  }
+}
+
+fn bundle_external(
+  make_absolute_externals_relative: MakeAbsoluteExternalsRelative,
+  paths: Option<OptionsPaths>,
+) -> String {
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/external/index.js".to_owned()],
+    make_absolute_externals_relative,
+    plugins: vec![Box::new(VendorExternal)],
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(
+    graph,
+    NormalizedOutputOptions {
+      paths,
+      ..Default::default()
+    },
+  );
+  let output = bundle.generate();
+  output.into_values().next().unwrap().code
+}
+
+#[test]
+fn absolute_externals_are_rendered_relative_to_chunk() {
+  let code = bundle_external(MakeAbsoluteExternalsRelative::Bool(true), None);
+  assert!(code.contains("\"./vendor/answer.js\""), "{}", code);
+  assert!(code.contains("\"lodash\""), "{}", code);
+}
+
+#[test]
+fn absolute_externals_are_kept_when_disabled() {
+  let code = bundle_external(MakeAbsoluteExternalsRelative::Bool(false), None);
+  let id = nodejs_path::resolve!("./tests/fixtures/external/vendor/answer.js");
+  assert!(code.contains(&id), "{}", code);
+}
+
+#[test]
+fn paths_rewrite_external_specifiers() {
+  let code = bundle_external(
+    MakeAbsoluteExternalsRelative::Bool(true),
+    Some(OptionsPaths::Map(HashMap::from([(
+      "lodash".to_string(),
+      "https://cdn.skypack.dev/lodash".to_string(),
+    )]))),
+  );
+  assert!(
+    code.contains("\"https://cdn.skypack.dev/lodash\""),
+    "{}",
+    code
+  );
+
+  let code = bundle_external(
+    MakeAbsoluteExternalsRelative::Bool(true),
+    Some(OptionsPaths::Fn(Box::new(|id| {
+      if id.ends_with("answer.js") {
+        "https://cdn.example.com/answer.js".to_string()
+      } else {
+        id.to_string()
+      }
+    }))),
+  );
+  assert!(
+    code.contains("\"https://cdn.example.com/answer.js\""),
+    "{}",
+    code
+  );
+  assert!(code.contains("\"lodash\""), "{}", code);
+}
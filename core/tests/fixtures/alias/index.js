@@ -0,0 +1,5 @@
+import { add } from '@app/math'
+import { VERSION } from 'version'
+import { greet } from '~/greet'
+
+console.log(add(1, 2), VERSION, greet)
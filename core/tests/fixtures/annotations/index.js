@@ -0,0 +1,19 @@
+import { defineThing } from './lib';
+
+/*#__NO_SIDE_EFFECTS__*/
+function localPure() {
+  console.log('local pure');
+}
+
+function impure() {
+  console.log('impure');
+}
+
+const element = /*#__PURE__*/ createElement('div');
+const instance = /*#__PURE__*/ new Foo();
+const wrapped = /*#__PURE__*/ wrap(sideEffect());
+const thing = defineThing();
+const local = localPure();
+const result = impure();
+
+export const used = 1;
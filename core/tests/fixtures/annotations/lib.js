@@ -0,0 +1,3 @@
+export /*#__NO_SIDE_EFFECTS__*/ function defineThing() {
+  console.log('define');
+}
@@ -0,0 +1,4 @@
+import { platform, fsShim } from "env-lib";
+import util from "util-lib";
+
+console.log(platform, fsShim, util);
@@ -0,0 +1,3 @@
+export const ENABLE_X = false;
+export const DEBUG = true;
+export const MODE = 'production';
@@ -0,0 +1,3 @@
+export function heavy() {
+  console.log('heavy module');
+}
@@ -0,0 +1,13 @@
+import { ENABLE_X, DEBUG, MODE } from './flags';
+import { heavy } from './heavy';
+import { light } from './light';
+
+if (ENABLE_X) {
+  heavy();
+} else {
+  light();
+}
+
+const label = DEBUG ? 'debug build' : 'release build';
+
+console.log(label, MODE === 'production' && 'production only', ENABLE_X && heavy());
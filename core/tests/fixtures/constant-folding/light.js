@@ -0,0 +1,3 @@
+export function light() {
+  console.log('light module');
+}
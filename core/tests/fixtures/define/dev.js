@@ -0,0 +1,3 @@
+export function devTools() {
+  console.log('dev tools');
+}
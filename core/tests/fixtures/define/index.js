@@ -0,0 +1,8 @@
+import { devTools } from './dev';
+import { mode, modes, read } from './shadowed';
+
+if (process.env.NODE_ENV !== 'production') {
+  devTools();
+}
+
+console.log(__DEV__, import.meta.env.MODE, read({ env: { NODE_ENV: 'shadowed' } }), mode, modes);
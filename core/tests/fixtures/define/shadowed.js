@@ -0,0 +1,9 @@
+// `process` is a parameter here, not the global.
+export function read(process) {
+  return process.env.NODE_ENV;
+}
+
+// The global is still replaced in the rest of the module, even in assignment targets.
+export const mode = process.env.NODE_ENV;
+export const modes = {};
+modes[process.env.NODE_ENV] = true;
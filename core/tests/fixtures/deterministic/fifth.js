@@ -0,0 +1,4 @@
+const value = 'fifth';
+let count = 1;
+
+export const helper = () => value + count;
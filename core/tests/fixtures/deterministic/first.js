@@ -0,0 +1,3 @@
+export const value = 'first';
+export const helper = () => value;
+export const x = 1, y = 2, z = 3;
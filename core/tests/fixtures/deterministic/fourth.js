@@ -0,0 +1,9 @@
+const value = 'fourth';
+let count = 0;
+
+export function helper() {
+  const value = 'inner';
+  return value + count++;
+}
+
+export { value as fourthValue };
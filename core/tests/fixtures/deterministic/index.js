@@ -0,0 +1,10 @@
+import * as first from './first';
+import * as alias from './first';
+import { value as second } from './second';
+import { value as third, helper } from './third';
+import { helper as fourthHelper, fourthValue } from './fourth';
+import { helper as fifthHelper } from './fifth';
+
+export { helper as thirdHelper } from './third';
+export const value = [first, alias, second, third, helper(), fourthHelper(), fourthValue, fifthHelper()];
+export const a = 1, b = 2, c = 3, d = 4, e = 5;
@@ -0,0 +1,2 @@
+const helper = 'second';
+export const value = helper;
@@ -0,0 +1,4 @@
+export const value = 'third';
+export function helper() {
+  return value;
+}
@@ -0,0 +1,3 @@
+import { answer } from "./foo";
+
+console.log(answer);
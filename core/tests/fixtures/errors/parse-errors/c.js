@@ -0,0 +1,4 @@
+const c = {
+  value: 'c'
+
+export default c;
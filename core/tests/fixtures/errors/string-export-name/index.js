@@ -0,0 +1,3 @@
+const foo = 1;
+
+export { foo as "foo-bar" };
@@ -0,0 +1,3 @@
+import missing from './missing.js';
+
+export default missing;
@@ -0,0 +1,3 @@
+// The same binding as `x` of `./a`
+export { x } from './a';
+export const y = 'b';
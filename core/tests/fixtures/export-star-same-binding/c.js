@@ -0,0 +1,4 @@
+import { x } from './a';
+
+// Also the same binding, through an import
+export { x };
@@ -0,0 +1,3 @@
+import { x, y } from './barrel';
+
+console.log(x, y);
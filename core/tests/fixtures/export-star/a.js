@@ -0,0 +1,3 @@
+export const conflict = 'a';
+export const fromA = 'a only';
+export default 'a default';
@@ -0,0 +1,2 @@
+export const conflict = 'b';
+export const deep = 'deep';
@@ -0,0 +1,4 @@
+import * as ns from './outer';
+import { fromA, deep, local } from './outer';
+
+console.log(ns, fromA, deep, local);
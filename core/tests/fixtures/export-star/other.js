@@ -0,0 +1,2 @@
+// `fromA` is the same binding as the one brought by `./barrel`.
+export * from './a';
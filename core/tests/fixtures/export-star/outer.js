@@ -0,0 +1,4 @@
+export * from './barrel';
+export * from './other';
+// Shadows `local` of `./barrel`
+export const local = 'outer local';
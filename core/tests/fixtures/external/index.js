@@ -0,0 +1,4 @@
+import { join } from 'lodash'
+import { answer } from './vendor/answer'
+
+console.log(join, answer)
@@ -0,0 +1,4 @@
+import { h } from "preact";
+import { config } from "config";
+
+console.log(h, config);
@@ -0,0 +1,7 @@
+import { h } from "preact";
+import { format } from "utils/format.js";
+import React from "react";
+import { config } from "./src/config.js";
+import { widget } from "./src/legacy/widget.js";
+
+console.log(h, format, React, config, widget);
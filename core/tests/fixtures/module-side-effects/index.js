@@ -0,0 +1,4 @@
+import { used } from './lib/index';
+import './local';
+
+console.log(used);
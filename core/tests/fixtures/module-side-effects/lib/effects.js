@@ -0,0 +1,2 @@
+console.log('effects module');
+export const effect = 'effect';
@@ -0,0 +1,3 @@
+export { used } from './used';
+export { unused } from './unused';
+export { effect } from './effects';
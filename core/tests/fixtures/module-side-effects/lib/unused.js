@@ -0,0 +1,2 @@
+console.log('unused module');
+export const unused = 'unused';
@@ -0,0 +1,2 @@
+console.log('used module');
+export const used = 'used';
@@ -0,0 +1,3 @@
+import './cycle-b';
+
+console.log('cycle a');
@@ -0,0 +1,3 @@
+import './cycle-a';
+
+console.log('entry b');
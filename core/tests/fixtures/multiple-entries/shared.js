@@ -0,0 +1,3 @@
+export const shared = 'shared';
+
+console.log('shared module');
@@ -0,0 +1 @@
+export const kept = 'kept'
@@ -0,0 +1,5 @@
+import * as utils from './utils'
+import * as escaped from './escaped'
+
+console.log(utils.used(), utils['alsoUsed'])
+console.log(escaped)
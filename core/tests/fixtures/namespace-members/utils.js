@@ -0,0 +1,3 @@
+export const used = () => 'used'
+export const alsoUsed = 'alsoUsed'
+export const unused = 'unused'
@@ -0,0 +1,3 @@
+import * as lib from './lib';
+
+console.log(lib);
@@ -0,0 +1,2 @@
+export const lib = 'lib';
+export const answer = 42;
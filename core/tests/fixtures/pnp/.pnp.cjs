@@ -0,0 +1,15 @@
+#!/usr/bin/env node
+/* eslint-disable */
+
+try {
+  Object.freeze({}).detectStrictMode = true;
+} catch (error) {
+  throw new Error(`The whole PnP file got strict-mode-ified, which is known to break (Emscripten libraries aren't strict mode). This usually happens when the file goes through Babel.`);
+}
+
+function $$SETUP_STATE(hydrateRuntimeState, basePath) {
+  return hydrateRuntimeState(JSON.parse(RAW_RUNTIME_STATE), {basePath: basePath || __dirname});
+}
+
+const RAW_RUNTIME_STATE =
+'{"__info": ["This file is automatically generated. Do not touch it, or risk your modifications being lost."], "dependencyTreeRoots": [{"name": "pnp-fixture", "reference": "workspace:."}], "enableTopLevelFallback": true, "ignorePatternData": "^\\\\.\\\\/ignored(\\\\/|$)", "fallbackExclusionList": [], "fallbackPool": [], "packageRegistryData": [[null, [[null, {"packageLocation": "./", "packageDependencies": [["hello", "npm:1.0.0"], ["left-pad", "npm:1.3.0"], ["peer-lib", "virtual:0a1b2c3d4e#npm:1.0.0"], ["pnp-fixture", "workspace:."]], "linkType": "SOFT"}]]], ["hello", [["npm:1.0.0", {"packageLocation": "./.yarn/unplugged/hello-npm-1.0.0-5f4dcc3b5a/node_modules/hello/", "packageDependencies": [["hello", "npm:1.0.0"]], "linkType": "HARD"}]]], ["left-pad", [["npm:1.3.0", {"packageLocation": "./.yarn/cache/left-pad-npm-1.3.0-f8da1b4e49-5ab4ab4d2c.zip/node_modules/left-pad/", "packageDependencies": [["left-pad", "npm:1.3.0"]], "linkType": "HARD"}]]], ["peer-lib", [["npm:1.0.0", {"packageLocation": "./.yarn/unplugged/peer-lib-npm-1.0.0-9f8e7d6c5b/node_modules/peer-lib/", "packageDependencies": [["hello", null], ["peer-lib", "npm:1.0.0"]], "linkType": "HARD"}], ["virtual:0a1b2c3d4e#npm:1.0.0", {"packageLocation": "./.yarn/__virtual__/peer-lib-virtual-0a1b2c3d4e/1/.yarn/unplugged/peer-lib-npm-1.0.0-9f8e7d6c5b/node_modules/peer-lib/", "packageDependencies": [["hello", "npm:1.0.0"], ["peer-lib", "virtual:0a1b2c3d4e#npm:1.0.0"]], "linkType": "HARD"}]]], ["pnp-fixture", [["workspace:.", {"packageLocation": "./", "packageDependencies": [["hello", "npm:1.0.0"], ["left-pad", "npm:1.3.0"], ["peer-lib", "virtual:0a1b2c3d4e#npm:1.0.0"], ["pnp-fixture", "workspace:."]], "linkType": "SOFT"}]]]]}';
@@ -0,0 +1,4 @@
+import leftPad from "left-pad";
+import { greet } from "hello";
+
+console.log(leftPad(greet(), 20));
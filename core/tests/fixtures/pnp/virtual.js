@@ -0,0 +1,4 @@
+import { shout } from "peer-lib";
+import plain from "./ignored/index.js";
+
+console.log(shout(), plain);
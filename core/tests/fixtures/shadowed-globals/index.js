@@ -0,0 +1,4 @@
+import { getValue } from './other';
+
+// `value` is a global here.
+console.log(value, getValue());
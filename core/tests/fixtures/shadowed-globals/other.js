@@ -0,0 +1,3 @@
+const value = 'local';
+
+export const getValue = () => value;
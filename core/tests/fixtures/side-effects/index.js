@@ -0,0 +1,37 @@
+const pureObject = { a: 1, b: [1, 2], c: typeof window, d: void 0 };
+const pureMath = Math.max(1, 2);
+const frozen = Object.freeze({ a: 1 });
+const pi = Math.PI;
+
+if (typeof window !== 'undefined') {
+  const unused = 1;
+}
+
+for (let i = 0; i < 10; i++) {}
+
+let count = 0;
+count++;
+count += 1;
+
+Object.polyfill = function () {};
+Math.sign = Math.sign || function () {};
+const target = {};
+target.written = 1;
+
+const impure = sideEffect();
+
+if (true) {
+  console.log('kept');
+}
+
+const object = createObject();
+const read = object.prop;
+
+const global = someGlobal;
+const safeGlobal = Promise;
+
+try {
+  const local = 1;
+} catch (e) {}
+
+export const used = 1;
@@ -0,0 +1,15 @@
+function inner() {
+  return 'inner'
+}
+
+function unused() {
+  return 'unused'
+}
+
+export function outer() {
+  return inner()
+}
+
+export function dropped() {
+  return unused()
+}
@@ -1,4 +1,7 @@
 import foo, { bar } from "./foo"
 import * as ns from "./namespace"
+import { outer } from "./helper"
 
-console.log(foo, bar, ns.a)
\ No newline at end of file
+console.log(foo, bar, ns.a, outer())
+
+export * from "./reexported"
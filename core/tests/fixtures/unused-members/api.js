@@ -0,0 +1,24 @@
+export class Api {
+  static getUser() {
+    return 'get user';
+  }
+
+  static listUsers() {
+    return 'list users';
+  }
+
+  fetch() {
+    return 'instance fetch';
+  }
+}
+
+// `Client` escapes by `new Client()`, so its members are kept.
+export class Client {
+  static create() {
+    return new Client();
+  }
+
+  request() {
+    return 'kept request';
+  }
+}
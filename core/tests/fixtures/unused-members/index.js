@@ -0,0 +1,13 @@
+import { Api, Client } from './api';
+
+const config = {
+  used: 'used config',
+  unused: 'unused config',
+  method() {
+    return 'unused method';
+  },
+};
+
+const escaped = { kept: 'kept property' };
+
+console.log(config.used, Api.getUser(), Client.create(), escaped);
@@ -0,0 +1,9 @@
+// `READY` is still `undefined` here.
+if (!READY) {
+  console.log('not ready yet');
+}
+var READY = true;
+
+// Throws a `TypeError`, since `create` is still `undefined` here.
+create();
+var create = /*#__NO_SIDE_EFFECTS__*/ () => ({});
@@ -0,0 +1,17 @@
+import { used, unused } from 'external-lib';
+
+export const self = this;
+
+export function getThis() {
+  return this;
+}
+
+export class Foo {
+  method() {
+    return this;
+  }
+}
+
+export const result = eval('used()');
+
+export const value = used();
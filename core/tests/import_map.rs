@@ -0,0 +1,86 @@
+use rolldown::{
+  bundle::Bundle,
+  graph::Graph,
+  types::{Alias, AliasFind, NormalizedInputOptions, NormalizedOutputOptions},
+  utils::import_map::{ImportMap, ImportMapTarget},
+};
+
+#[test]
+fn import_map_imports_and_scopes() {
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/import-map/index.js".to_owned()],
+    import_map: Some(ImportMap::from_file("./tests/fixtures/import-map/importmap.json").unwrap()),
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, NormalizedOutputOptions::default());
+  let output = bundle.generate();
+  let code = &output.values().next().unwrap().code;
+
+  // Root-relative address
+  assert!(code.contains("preact-modern:"), "{}", code);
+  // Scoped to `/src/legacy/`
+  assert!(code.contains("preact-legacy:"), "{}", code);
+  // Prefix key
+  assert!(code.contains("formatted:"), "{}", code);
+  // Relative key
+  assert!(code.contains("config-prod"), "{}", code);
+  assert!(!code.contains("config-dev"), "{}", code);
+  // URL address stays external
+  assert!(code.contains("\"https://esm.sh/react@17\""), "{}", code);
+}
+
+#[test]
+fn import_map_blocked_and_unmapped_specifiers() {
+  let import_map = ImportMap::from_json(
+    "/project",
+    r#"{ "imports": { "blocked": null, "pkg/": "./pkg/", "bare": "lodash" } }"#,
+  )
+  .unwrap();
+  let importer = "/project/index.js";
+  assert_eq!(
+    import_map.resolve("blocked", importer),
+    Some(ImportMapTarget::Blocked)
+  );
+  assert_eq!(
+    import_map.resolve("pkg/a.js", importer),
+    Some(ImportMapTarget::Path("/project/pkg/a.js".to_string()))
+  );
+  // Bare addresses are invalid.
+  assert_eq!(
+    import_map.resolve("bare", importer),
+    Some(ImportMapTarget::Blocked)
+  );
+  assert_eq!(import_map.resolve("lodash", importer), None);
+}
+
+#[test]
+fn import_map_applies_before_alias() {
+  let mut graph = Graph::new(NormalizedInputOptions {
+    // Mapped to `./app.js`
+    input: vec!["app".to_owned()],
+    import_map: Some(ImportMap::from_file("./tests/fixtures/import-map/importmap.json").unwrap()),
+    alias: vec![
+      Alias {
+        find: AliasFind::Exact("preact".to_string()),
+        replacement: nodejs_path::resolve!("./tests/fixtures/import-map/vendor/preact-legacy.js"),
+      },
+      Alias {
+        find: AliasFind::Exact("config".to_string()),
+        replacement: nodejs_path::resolve!("./tests/fixtures/import-map/src/config.js"),
+      },
+    ],
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, NormalizedOutputOptions::default());
+  let output = bundle.generate();
+  let code = &output.values().next().unwrap().code;
+
+  // The import map takes precedence over the alias of `preact`.
+  assert!(code.contains("preact-modern:"), "{}", code);
+  assert!(!code.contains("preact-legacy:"), "{}", code);
+  // `./src/config.js` is remapped by the import map, but not when it's brought by an alias.
+  assert!(code.contains("config-dev"), "{}", code);
+  assert!(!code.contains("config-prod"), "{}", code);
+}
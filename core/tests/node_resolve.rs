@@ -0,0 +1,103 @@
+use rolldown::{
+  bundle::Bundle,
+  graph::Graph,
+  plugins::{
+    node_resolve::{NodeResolveOptions, NodeResolver},
+    pnp::PnpManifest,
+  },
+  types::{NormalizedInputOptions, NormalizedOutputOptions},
+};
+
+fn bundle(input: &str, options: NodeResolveOptions) -> String {
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec![input.to_owned()],
+    plugins: vec![Box::new(NodeResolver::new(options))],
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, NormalizedOutputOptions::default());
+  let output = bundle.generate();
+  output.into_values().next().unwrap().code
+}
+
+fn bundle_with_main_fields(main_fields: &[&str]) -> String {
+  bundle(
+    "./tests/fixtures/browser/index.js",
+    NodeResolveOptions {
+      main_fields: main_fields.iter().map(|field| field.to_string()).collect(),
+      ..Default::default()
+    },
+  )
+}
+
+#[test]
+fn main_fields_are_tried_in_order() {
+  let code = bundle_with_main_fields(&["module", "main"]);
+  assert!(code.contains("node-module:"), "{}", code);
+  assert!(code.contains("internal-node"), "{}", code);
+  assert!(code.contains("util-node"), "{}", code);
+  assert!(code.contains("\"fs\""), "{}", code);
+
+  let code = bundle_with_main_fields(&["main"]);
+  assert!(code.contains("node-main"), "{}", code);
+}
+
+#[test]
+fn browser_field_remaps_files_and_modules() {
+  let code = bundle_with_main_fields(&["browser", "module", "main"]);
+  // `browser` field in string form
+  assert!(code.contains("util-browser"), "{}", code);
+  // `./lib/module.js` -> `./lib/browser.js`
+  assert!(code.contains("browser:"), "{}", code);
+  assert!(!code.contains("node-module:"), "{}", code);
+  // `./lib/internal.js` -> `./lib/internal-browser.js`
+  assert!(code.contains("internal-browser"), "{}", code);
+  assert!(!code.contains("internal-node"), "{}", code);
+  // `fs` -> `false`
+  assert!(!code.contains("\"fs\""), "{}", code);
+}
+
+#[test]
+fn pnp_manifest_resolves_zipped_and_unplugged_packages() {
+  [
+    "./tests/fixtures/pnp/.pnp.data.json",
+    "./tests/fixtures/pnp/.pnp.cjs",
+  ]
+  .iter()
+  .for_each(|manifest| {
+    let code = bundle(
+      "./tests/fixtures/pnp/index.js",
+      NodeResolveOptions {
+        pnp: Some(PnpManifest::from_file(manifest).unwrap()),
+        ..Default::default()
+      },
+    );
+    assert!(code.contains("left-pad-zip:"), "{}", code);
+    assert!(code.contains("repeat"), "{}", code);
+    assert!(code.contains("hello-unplugged"), "{}", code);
+    assert!(!code.contains("\"left-pad\""), "{}", code);
+  });
+}
+
+#[test]
+fn pnp_manifest_resolves_virtual_packages_and_ignored_paths() {
+  [
+    "./tests/fixtures/pnp/.pnp.data.json",
+    "./tests/fixtures/pnp/.pnp.cjs",
+  ]
+  .iter()
+  .for_each(|manifest| {
+    let code = bundle(
+      "./tests/fixtures/pnp/virtual.js",
+      NodeResolveOptions {
+        pnp: Some(PnpManifest::from_file(manifest).unwrap()),
+        ..Default::default()
+      },
+    );
+    // `peer-lib` is read through its virtual location, along with its relative imports and peers.
+    assert!(code.contains("-peer-virtual"), "{}", code);
+    assert!(code.contains("hello-unplugged"), "{}", code);
+    // `ignored/` matches `ignorePatternData`, so `plain` is found in `node_modules`.
+    assert!(code.contains("plain-node-modules"), "{}", code);
+  });
+}
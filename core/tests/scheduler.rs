@@ -0,0 +1,53 @@
+use rolldown::{
+  bundle::Bundle,
+  graph::Graph,
+  types::{NormalizedInputOptions, NormalizedOutputOptions},
+};
+
+fn build_with_threads(max_parallel_file_reads: Option<usize>) -> Vec<String> {
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/preact/index.js".to_owned()],
+    max_parallel_file_reads,
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut ids = graph
+    .module_by_id
+    .keys()
+    .map(|id| id.to_string())
+    .collect::<Vec<_>>();
+  ids.sort();
+  ids
+}
+
+#[test]
+fn module_graph_is_independent_of_thread_count() {
+  let single_thread = build_with_threads(Some(1));
+  assert!(single_thread.len() > 10, "{:?}", single_thread);
+  assert_eq!(single_thread, build_with_threads(Some(4)));
+  assert_eq!(single_thread, build_with_threads(None));
+}
+
+fn generate_with_threads(max_parallel_file_reads: Option<usize>) -> String {
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/deterministic/index.js".to_owned()],
+    max_parallel_file_reads,
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, NormalizedOutputOptions::default());
+  let output = bundle.generate();
+  output.values().next().unwrap().code.clone()
+}
+
+#[test]
+fn output_is_independent_of_thread_scheduling() {
+  let expected = generate_with_threads(Some(1));
+  // Several modules declare `value`, `helper` and `count`, so most of them are renamed.
+  assert!(expected.contains("count$"), "{}", expected);
+  assert!(expected.contains("value$"), "{}", expected);
+  (0..20).for_each(|run| {
+    let code = generate_with_threads(Some(run % 4 + 1));
+    assert_eq!(expected, code, "run {} differs", run);
+  });
+}
@@ -9,7 +9,7 @@ fn basic() {
   let mut graph = Graph::from_single_entry("./tests/fixtures/conflicted/index.js".to_owned());
   // let mut graph =
   //   GraphContainer::from_single_entry("../node_modules/lodash-es/lodash.js".to_owned());
-  graph.build();
+  graph.build().unwrap();
   // let mut bundle = Bundle::new(graph);
 
   // let output = bundle.generate();
@@ -0,0 +1,270 @@
+use rolldown::{
+  bundle::Bundle,
+  graph::Graph,
+  types::{
+    ModuleSideEffects, NormalizedInputOptions, NormalizedOutputOptions,
+    NormalizedTreeshakingOptions, PropertyReadSideEffects,
+  },
+};
+use serde_json::json;
+
+fn bundle(input: &str) -> String {
+  bundle_with(NormalizedInputOptions {
+    input: vec![input.to_owned()],
+    treeshake: Some(Default::default()),
+    ..Default::default()
+  })
+}
+
+fn bundle_with(input_options: NormalizedInputOptions) -> String {
+  let mut graph = Graph::new(input_options);
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, NormalizedOutputOptions::default());
+  let output = bundle.generate();
+  output.values().next().unwrap().code.clone()
+}
+
+#[test]
+fn include_referenced_declarations() {
+  let code = bundle("./tests/fixtures/tree-shaking/index.js");
+  // Read by the side effect `a3()`
+  assert!(code.contains("const a3 ="), "{}", code);
+  // Exported by the entry
+  assert!(code.contains("exported_local_a = 1"), "{}", code);
+  // Unused
+  assert!(!code.contains("local_b"), "{}", code);
+  assert!(!code.contains("= 2"), "{}", code);
+}
+
+#[test]
+fn include_transitively() {
+  let code = bundle("./tests/fixtures/tree-shaking-advanced/index.js");
+  // `inner` is only used by `outer`, which is used by the entry.
+  assert!(code.contains("function outer"), "{}", code);
+  assert!(code.contains("function inner"), "{}", code);
+  assert!(!code.contains("function dropped"), "{}", code);
+  assert!(!code.contains("function unused"), "{}", code);
+  // `export * from "./bar"` of `./foo`
+  assert!(code.contains("bar = 1"), "{}", code);
+  // `ns.a` is resolved to `a`, so the namespace object isn't needed.
+  assert!(code.contains("a = 1"), "{}", code);
+  assert!(!code.contains("b = 2"), "{}", code);
+  // `export *` of the entry
+  assert!(code.contains("reexported = 'reexported'"), "{}", code);
+}
+
+#[test]
+fn drop_statements_without_side_effects() {
+  let code = bundle("./tests/fixtures/side-effects/index.js");
+  assert!(code.contains("sideEffect()"), "{}", code);
+  assert!(code.contains("console.log('kept')"), "{}", code);
+  // Getters might be triggered.
+  assert!(code.contains("object.prop"), "{}", code);
+  // Unknown globals might not exist.
+  assert!(code.contains("someGlobal"), "{}", code);
+  // Writes to top-level bindings and properties are visible to other statements.
+  assert!(code.contains("let count = 0"), "{}", code);
+  assert!(code.contains("count++"), "{}", code);
+  assert!(code.contains("count += 1"), "{}", code);
+  assert!(code.contains("Object.polyfill ="), "{}", code);
+  assert!(code.contains("Math.sign ="), "{}", code);
+  assert!(code.contains("target.written = 1"), "{}", code);
+  [
+    "pureObject",
+    "pureMath",
+    "frozen",
+    "Math.PI",
+    "typeof window",
+    "i++",
+    "Promise",
+  ]
+  .iter()
+  .for_each(|pure| assert!(!code.contains(pure), "{}", code));
+}
+
+#[test]
+fn property_reads_without_side_effects() {
+  let code = bundle_with(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/side-effects/index.js".to_owned()],
+    treeshake: Some(NormalizedTreeshakingOptions {
+      property_read_side_effects: PropertyReadSideEffects::Bool(false),
+      ..Default::default()
+    }),
+    ..Default::default()
+  });
+  assert!(!code.contains("object.prop"), "{}", code);
+  assert!(code.contains("sideEffect()"), "{}", code);
+  // Assignments are side effects regardless of property reads.
+  assert!(code.contains("Object.polyfill ="), "{}", code);
+  assert!(code.contains("target.written = 1"), "{}", code);
+}
+
+#[test]
+fn respect_pure_annotations() {
+  let code = bundle("./tests/fixtures/annotations/index.js");
+  assert!(code.contains("sideEffect()"), "{}", code);
+  assert!(code.contains("impure()"), "{}", code);
+  ["createElement", "new Foo", "defineThing", "localPure"]
+    .iter()
+    .for_each(|pure| assert!(!code.contains(pure), "{}", code));
+}
+
+#[test]
+fn ignore_annotations_if_disabled() {
+  let code = bundle_with(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/annotations/index.js".to_owned()],
+    treeshake: Some(NormalizedTreeshakingOptions {
+      annotations: false,
+      ..Default::default()
+    }),
+    ..Default::default()
+  });
+  ["createElement", "new Foo", "defineThing", "localPure"]
+    .iter()
+    .for_each(|impure| assert!(code.contains(impure), "{}", code));
+}
+
+#[test]
+fn side_effects_field_of_package_json() {
+  let code = bundle("./tests/fixtures/module-side-effects/index.js");
+  assert!(code.contains("used module"), "{}", code);
+  // Listed in `sideEffects`
+  assert!(code.contains("effects module"), "{}", code);
+  assert!(!code.contains("unused module"), "{}", code);
+  assert!(code.contains("local module"), "{}", code);
+}
+
+#[test]
+fn module_side_effects_option() {
+  let code = bundle_with(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/module-side-effects/index.js".to_owned()],
+    treeshake: Some(NormalizedTreeshakingOptions {
+      module_side_effects: ModuleSideEffects::Predicate(Box::new(|id, _| {
+        !id.ends_with("local.js")
+      })),
+      ..Default::default()
+    }),
+    ..Default::default()
+  });
+  assert!(!code.contains("local module"), "{}", code);
+  // `package.json` takes precedence.
+  assert!(code.contains("effects module"), "{}", code);
+  assert!(!code.contains("unused module"), "{}", code);
+}
+
+#[test]
+fn treeshake_presets() {
+  let bundle_with_preset = |treeshake: NormalizedTreeshakingOptions| {
+    bundle_with(NormalizedInputOptions {
+      input: vec!["./tests/fixtures/side-effects/index.js".to_owned()],
+      treeshake: Some(treeshake),
+      ..Default::default()
+    })
+  };
+
+  let code = bundle_with_preset(NormalizedTreeshakingOptions::safest());
+  assert!(code.contains("object.prop"), "{}", code);
+  assert!(code.contains("someGlobal"), "{}", code);
+  assert!(code.contains("try {"), "{}", code);
+
+  let code = bundle_with_preset(NormalizedTreeshakingOptions::recommended());
+  assert!(code.contains("object.prop"), "{}", code);
+  assert!(!code.contains("someGlobal"), "{}", code);
+  assert!(code.contains("try {"), "{}", code);
+
+  let code = bundle_with_preset(NormalizedTreeshakingOptions::smallest());
+  assert!(!code.contains("object.prop"), "{}", code);
+  assert!(!code.contains("someGlobal"), "{}", code);
+  assert!(!code.contains("try {"), "{}", code);
+  assert!(code.contains("sideEffect()"), "{}", code);
+}
+
+#[test]
+fn correct_var_value_before_declaration() {
+  let bundle_with_preset = |treeshake: NormalizedTreeshakingOptions| {
+    bundle_with(NormalizedInputOptions {
+      input: vec!["./tests/fixtures/var-before-declaration/index.js".to_owned()],
+      treeshake: Some(treeshake),
+      ..Default::default()
+    })
+  };
+
+  let code = bundle_with_preset(NormalizedTreeshakingOptions::safest());
+  assert!(code.contains("'not ready yet'"), "{}", code);
+  assert!(code.contains("create()"), "{}", code);
+
+  // `var` bindings are assumed to be read after their declarations.
+  let code = bundle_with_preset(NormalizedTreeshakingOptions::recommended());
+  assert!(!code.contains("'not ready yet'"), "{}", code);
+  assert!(!code.contains("create()"), "{}", code);
+}
+
+#[test]
+fn resolve_namespace_members() {
+  let code = bundle("./tests/fixtures/namespace-members/index.js");
+  assert!(code.contains("console.log(used(), alsoUsed)"), "{}", code);
+  assert!(!code.contains("'unused'"), "{}", code);
+  assert!(!code.contains("const utils"), "{}", code);
+  // The namespace object is needed once it escapes.
+  assert!(code.contains("const escaped"), "{}", code);
+  assert!(code.contains("'kept'"), "{}", code);
+}
+
+#[test]
+fn namespace_name_avoids_declared_bindings() {
+  // `lib.js` declares `lib` itself, which is the name suggested for its namespace.
+  let code = bundle("./tests/fixtures/namespace-name/index.js");
+  assert!(code.contains("lib$1"), "{}", code);
+}
+
+#[test]
+fn remove_unused_members() {
+  let code = bundle("./tests/fixtures/unused-members/index.js");
+  assert!(code.contains("'used config'"), "{}", code);
+  assert!(code.contains("'get user'"), "{}", code);
+  assert!(code.contains("'kept property'"), "{}", code);
+  assert!(code.contains("'kept request'"), "{}", code);
+  [
+    "'unused config'",
+    "'unused method'",
+    "'list users'",
+    "'instance fetch'",
+  ]
+  .iter()
+  .for_each(|unused| assert!(!code.contains(unused), "{}", code));
+}
+
+#[test]
+fn fold_constants_across_modules() {
+  let code = bundle("./tests/fixtures/constant-folding/index.js");
+  assert!(code.contains("'light module'"), "{}", code);
+  assert!(code.contains("'debug build'"), "{}", code);
+  assert!(code.contains("'production only'"), "{}", code);
+  // Only used in dead branches
+  assert!(!code.contains("'heavy module'"), "{}", code);
+  assert!(!code.contains("'release build'"), "{}", code);
+}
+
+#[test]
+fn replace_defined_expressions() {
+  let code = bundle_with(NormalizedInputOptions {
+    input: vec!["./tests/fixtures/define/index.js".to_owned()],
+    treeshake: Some(Default::default()),
+    define: [
+      ("process.env.NODE_ENV", json!("production")),
+      ("__DEV__", json!(false)),
+      ("import.meta.env.MODE", json!("production")),
+    ]
+    .into_iter()
+    .map(|(expr, value)| (expr.to_owned(), value))
+    .collect(),
+    ..Default::default()
+  });
+  // The dead branch is dropped along with the module it imports.
+  assert!(!code.contains("dev tools"), "{}", code);
+  assert!(code.contains("console.log(false, "), "{}", code);
+  assert!(!code.contains("import.meta"), "{}", code);
+  // Only shadowed by the parameter of `read`
+  assert_eq!(code.matches("process.env.NODE_ENV").count(), 1, "{}", code);
+  assert!(code.contains("modes["), "{}", code);
+}
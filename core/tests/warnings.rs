@@ -0,0 +1,70 @@
+use std::sync::{Arc, Mutex};
+
+use rolldown::{
+  bundle::Bundle,
+  graph::Graph,
+  types::{NormalizedInputOptions, NormalizedOutputOptions},
+  warning::{Warning, WarningCode},
+};
+
+fn collect_warnings(input: &str, treeshake: bool) -> Vec<Warning> {
+  let warnings = Arc::new(Mutex::new(vec![]));
+  let collected = warnings.clone();
+  let mut graph = Graph::new(NormalizedInputOptions {
+    input: vec![input.to_owned()],
+    treeshake: treeshake.then(Default::default),
+    on_warn: Some(Box::new(move |warning: &Warning| {
+      collected.lock().unwrap().push(warning.clone())
+    })),
+    ..Default::default()
+  });
+  graph.build().unwrap();
+  let mut bundle = Bundle::new(graph, NormalizedOutputOptions::default());
+  bundle.generate();
+  let warnings = warnings.lock().unwrap().clone();
+  warnings
+}
+
+#[test]
+fn module_level_warnings() {
+  let warnings = collect_warnings("./tests/fixtures/warnings/index.js", false);
+  let codes = warnings.iter().map(|w| w.code).collect::<Vec<_>>();
+
+  // Only the top-level `this` is reported.
+  let this_warnings = warnings
+    .iter()
+    .filter(|w| w.code == WarningCode::ThisIsUndefined)
+    .collect::<Vec<_>>();
+  assert_eq!(this_warnings.len(), 1, "{:?}", codes);
+  let loc = this_warnings[0].loc.clone().unwrap();
+  assert_eq!((loc.line, loc.column), (3, 20));
+  assert!(this_warnings[0]
+    .id
+    .as_ref()
+    .unwrap()
+    .ends_with("warnings/index.js"));
+
+  assert!(codes.contains(&WarningCode::Eval), "{:?}", codes);
+
+  let unused = warnings
+    .iter()
+    .find(|w| w.code == WarningCode::UnusedExternalImport)
+    .unwrap();
+  assert!(
+    unused
+      .message
+      .starts_with("\"unused\" imported from external module \"external-lib\""),
+    "{}",
+    unused
+  );
+  assert!(!codes.contains(&WarningCode::EmptyBundle), "{:?}", codes);
+}
+
+#[test]
+fn empty_bundle() {
+  let warnings = collect_warnings("./tests/fixtures/warnings/empty.js", true);
+  assert_eq!(
+    warnings.iter().map(|w| w.code).collect::<Vec<_>>(),
+    vec![WarningCode::EmptyBundle]
+  );
+}
@@ -25,7 +25,9 @@ impl Task for Rolldown {
 
   fn compute(&mut self) -> Result<Self::Output> {
     let mut graph = rolldown::graph::Graph::from_single_entry(self.entry.clone());
-    graph.build();
+    graph
+      .build()
+      .map_err(|err| napi::Error::new(napi::Status::GenericFailure, err.to_string()))?;
     let mut bundle = rolldown::bundle::Bundle::new(graph, Default::default());
     let generated = bundle.generate();
     Ok(generated.values().next().unwrap().code.clone())